@@ -0,0 +1,790 @@
+//! Card-location deduction from one player's point of view.
+//!
+//! Unseen cards are matched to the places that still hold cards this player cannot see
+//! (other hands, the stock and discards that weren't recorded), respecting hand counts and
+//! proven voids. A card is pinned
+//! to a place when every consistent deal puts it there.
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{Card, GameState, PlayerId, Rank, Suit};
+
+/// Search steps allowed for exact card probabilities before falling back to sampling.
+const EXACT_LIMIT: usize = 200_000;
+
+/// A place holding cards that are hidden from the perspective player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Location {
+    Hand { player: PlayerId },
+    Stock,
+    /// Discarded cards that weren't recorded (e.g. when entering a game in progress)
+    Discard,
+}
+
+/// Proof that a player holds no card of `suit` (below `below`, if set), e.g. from announcing
+/// their lowest trump at the start of the game. It lapses when the player draws from the stock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
+pub struct Void {
+    pub player: PlayerId,
+    pub suit: Suit,
+    /// Only cards of `suit` ranked below this are excluded (None: the whole suit)
+    #[serde(default)]
+    pub below: Option<Rank>,
+}
+
+impl Void {
+    /// Check if this void rules out `card` for its player.
+    pub fn excludes(&self, card: &Card) -> bool {
+        !card.is_unknown()
+            && !card.is_joker()
+            && card.suit() == self.suit
+            && self.below.is_none_or(|r| card.rank() < r)
+    }
+}
+
+/// What is known about one unseen card (all copies of it with several decks).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
+pub struct CardDeduction {
+    /// The card (public)
+    pub card: Card,
+    /// Number of unseen copies
+    pub copies: usize,
+    /// Places that may hold a copy in some consistent deal
+    pub possible: Vec<Location>,
+    /// Copies that every consistent deal puts at a place
+    pub forced: Vec<(Location, usize)>,
+}
+
+/// Card-location deduction for one perspective (see `GameState::deduce`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
+pub struct Deduction {
+    pub perspective: PlayerId,
+    /// Number of hidden cards at each place
+    pub slots: Vec<(Location, usize)>,
+    /// Unseen cards, in deck order
+    pub cards: Vec<CardDeduction>,
+}
+
+impl Deduction {
+    /// Whether the location of every unseen card is known.
+    pub fn is_complete(&self) -> bool {
+        self.cards
+            .iter()
+            .all(|c| c.forced.iter().map(|(_, n)| n).sum::<usize>() == c.copies)
+    }
+
+    /// Cards proven to be at `location` (one entry per copy).
+    pub fn forced_at(&self, location: Location) -> Vec<Card> {
+        let mut out = Vec::new();
+        for c in &self.cards {
+            for &(loc, n) in &c.forced {
+                if loc == location {
+                    out.extend(std::iter::repeat_n(c.card, n));
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Where one unseen card is likely to be (see `GameState::card_probabilities`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
+pub struct CardProbability {
+    /// The card (public)
+    pub card: Card,
+    /// Number of unseen copies
+    pub copies: usize,
+    /// Chance of each hidden place holding the card (share of its copies), in `slots` order
+    pub locations: Vec<(Location, f64)>,
+}
+
+/// Card-location probabilities for one perspective, for a card-tracking heatmap.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
+pub struct CardProbabilities {
+    pub perspective: PlayerId,
+    /// Number of hidden cards at each place
+    pub slots: Vec<(Location, usize)>,
+    /// Computed exactly over all consistent deals (otherwise estimated from samples)
+    pub exact: bool,
+    /// Number of sampled deals (0 when exact)
+    pub samples: usize,
+    /// Unseen cards, in deck order
+    pub cards: Vec<CardProbability>,
+}
+
+/// Hidden cards from one perspective: the unseen pool and where the hidden slots are.
+/// Cards ruled out of the same places are interchangeable, so the dealing constraints
+/// are solved per class of such cards rather than per card.
+struct Hidden {
+    locations: Vec<Location>,
+    /// Hidden positions per location: hand card, stock or discard indices
+    positions: Vec<Vec<usize>>,
+    /// Distinct unseen cards, their number of copies and their class
+    cards: Vec<(Card, usize, usize)>,
+    /// `classes[k][l]`: cards of class `k` may be at location `l` (not ruled out by a void)
+    classes: Vec<Vec<bool>>,
+}
+
+impl Hidden {
+    fn new(state: &GameState, perspective: PlayerId) -> Self {
+        let mut known: Vec<Card> = Vec::new();
+        let mut locations = Vec::new();
+        let mut positions = Vec::new();
+
+        for (i, hand) in state.hands.iter().enumerate() {
+            let mine = i == perspective.index();
+            let mut hidden = Vec::new();
+            for (j, card) in hand.iter().enumerate() {
+                // Card is known if Public, or Private in perspective's hand
+                if card.is_public() || (card.is_private() && mine) {
+                    known.push(*card);
+                } else {
+                    hidden.push(j);
+                }
+            }
+            if !hidden.is_empty() {
+                locations.push(Location::Hand { player: PlayerId::from_index(i) });
+                positions.push(hidden);
+            }
+        }
+
+        let mut hidden_stock = Vec::new();
+        for (i, card) in state.stock.iter().enumerate() {
+            if card.is_public() {
+                known.push(*card);
+            } else {
+                hidden_stock.push(i);
+            }
+        }
+        if !hidden_stock.is_empty() {
+            locations.push(Location::Stock);
+            positions.push(hidden_stock);
+        }
+
+        let mut hidden_discard = Vec::new();
+        for (i, card) in state.discard.iter().enumerate() {
+            if card.is_unknown() {
+                hidden_discard.push(i);
+            } else {
+                known.push(*card);
+            }
+        }
+        if !hidden_discard.is_empty() {
+            locations.push(Location::Discard);
+            positions.push(hidden_discard);
+        }
+
+        for pile in &state.table {
+            known.push(pile.attack);
+            known.extend(pile.defense);
+        }
+
+        let mut classes: Vec<Vec<bool>> = Vec::new();
+        let mut cards: Vec<(Card, usize, usize)> = Vec::new();
+        for card in state.unseen(&known) {
+            if let Some((_, n, _)) = cards.iter_mut().find(|(c, _, _)| c.matches(&card)) {
+                *n += 1;
+                continue;
+            }
+            let allowed: Vec<bool> = locations
+                .iter()
+                .map(|loc| match loc {
+                    Location::Hand { player } => {
+                        !state.voids.iter().any(|v| v.player == *player && v.excludes(&card))
+                    }
+                    Location::Stock | Location::Discard => true,
+                })
+                .collect();
+            let class = classes.iter().position(|k| *k == allowed).unwrap_or_else(|| {
+                classes.push(allowed);
+                classes.len() - 1
+            });
+            cards.push((card, 1, class));
+        }
+
+        let mut hidden = Self { locations, positions, cards, classes };
+        // Voids that contradict the position (e.g. a wrong manual entry) are dropped
+        if !hidden.feasible(&hidden.supply(), &hidden.demand(), None) {
+            hidden.classes = vec![vec![true; hidden.locations.len()]];
+            for card in &mut hidden.cards {
+                card.2 = 0;
+            }
+        }
+        hidden
+    }
+
+    /// Number of unseen cards per class.
+    fn supply(&self) -> Vec<usize> {
+        let mut supply = vec![0; self.classes.len()];
+        for &(_, n, k) in &self.cards {
+            supply[k] += n;
+        }
+        supply
+    }
+
+    /// Number of hidden slots per location.
+    fn demand(&self) -> Vec<usize> {
+        self.positions.iter().map(|p| p.len()).collect()
+    }
+
+    /// Check if the classes can be dealt to the locations, optionally capping the cards
+    /// of class `k` at location `l` with `cap = Some((k, l, max))`. If the numbers of cards
+    /// and hidden slots differ (an inconsistent manual position), the smaller side must be used up.
+    fn feasible(&self, supply: &[usize], demand: &[usize], cap: Option<(usize, usize, usize)>) -> bool {
+        let total = supply.iter().sum::<usize>().min(demand.iter().sum());
+
+        // Max flow: source -> classes -> locations -> sink
+        let (nk, nl) = (supply.len(), demand.len());
+        let (source, sink) = (0, nk + nl + 1);
+        let n = nk + nl + 2;
+        let mut residual = vec![vec![0usize; n]; n];
+        for k in 0..nk {
+            residual[source][1 + k] = supply[k];
+            for l in 0..nl {
+                if self.classes[k][l] {
+                    residual[1 + k][1 + nk + l] = match cap {
+                        Some((ck, cl, max)) if ck == k && cl == l => max,
+                        _ => supply[k],
+                    };
+                }
+            }
+        }
+        for l in 0..nl {
+            residual[1 + nk + l][sink] = demand[l];
+        }
+
+        let mut flow = 0;
+        loop {
+            // Breadth-first search for an augmenting path
+            let mut prev = vec![usize::MAX; n];
+            prev[source] = source;
+            let mut queue = std::collections::VecDeque::from([source]);
+            while let Some(u) = queue.pop_front() {
+                for v in 0..n {
+                    if prev[v] == usize::MAX && residual[u][v] > 0 {
+                        prev[v] = u;
+                        queue.push_back(v);
+                    }
+                }
+            }
+            if prev[sink] == usize::MAX {
+                break;
+            }
+            let mut bottleneck = usize::MAX;
+            let mut v = sink;
+            while v != source {
+                bottleneck = bottleneck.min(residual[prev[v]][v]);
+                v = prev[v];
+            }
+            let mut v = sink;
+            while v != source {
+                residual[prev[v]][v] -= bottleneck;
+                residual[v][prev[v]] += bottleneck;
+                v = prev[v];
+            }
+            flow += bottleneck;
+        }
+        flow == total
+    }
+
+    fn deduce(&self, perspective: PlayerId) -> Deduction {
+        let (supply, demand) = (self.supply(), self.demand());
+
+        // Per class and location: can it hold one of the class's cards, and how many it holds at least
+        let mut possible = vec![vec![false; self.locations.len()]; self.classes.len()];
+        let mut min = vec![vec![0; self.locations.len()]; self.classes.len()];
+        for k in 0..self.classes.len() {
+            for l in 0..self.locations.len() {
+                if !self.classes[k][l] || demand[l] == 0 || supply[k] == 0 {
+                    continue;
+                }
+                let (mut s, mut d) = (supply.clone(), demand.clone());
+                s[k] -= 1;
+                d[l] -= 1;
+                possible[k][l] = self.feasible(&s, &d, None);
+                if possible[k][l] {
+                    // Binary search for the smallest cap that still allows a full deal
+                    let (mut lo, mut hi) = (0, supply[k].min(demand[l]));
+                    while lo < hi {
+                        let mid = (lo + hi) / 2;
+                        if self.feasible(&supply, &demand, Some((k, l, mid))) {
+                            hi = mid;
+                        } else {
+                            lo = mid + 1;
+                        }
+                    }
+                    min[k][l] = lo;
+                }
+            }
+        }
+
+        let cards = self
+            .cards
+            .iter()
+            .map(|&(card, copies, k)| {
+                let mut card_possible = Vec::new();
+                let mut forced = Vec::new();
+                for (l, &location) in self.locations.iter().enumerate() {
+                    if !possible[k][l] {
+                        continue;
+                    }
+                    card_possible.push(location);
+                    // The other cards of the class can fill at most `supply - min` slots elsewhere
+                    let at_least = copies.saturating_sub(supply[k] - min[k][l]);
+                    if at_least > 0 {
+                        forced.push((location, at_least));
+                    }
+                }
+                CardDeduction { card, copies, possible: card_possible, forced }
+            })
+            .collect();
+
+        Deduction {
+            perspective,
+            slots: self.locations.iter().copied().zip(demand).collect(),
+            cards,
+        }
+    }
+
+    /// Exact expected number of cards of each class at each location, over all consistent deals
+    /// (equally likely). Enumerates how many cards of each class go where, so it gives up (None)
+    /// past `EXACT_LIMIT` such splits or if the cards don't fill the hidden slots exactly.
+    fn expected_exact(&self) -> Option<Vec<Vec<f64>>> {
+        let (supply, demand) = (self.supply(), self.demand());
+        if supply.iter().sum::<usize>() != demand.iter().sum::<usize>() {
+            return None;
+        }
+
+        struct Search<'a> {
+            hidden: &'a Hidden,
+            supply: Vec<usize>,
+            demand: Vec<usize>,
+            split: Vec<Vec<usize>>,
+            /// Sum of weights and of weight * split over the complete splits
+            total: f64,
+            expected: Vec<Vec<f64>>,
+            visited: usize,
+        }
+
+        impl Search<'_> {
+            /// Split the cards of class `k` from location `l` on, with `left` still to place
+            /// and `weight` the product of 1 / n! over the counts placed so far.
+            fn run(&mut self, k: usize, l: usize, left: usize, weight: f64) -> bool {
+                self.visited += 1;
+                if self.visited > EXACT_LIMIT {
+                    return false;
+                }
+                if k == self.supply.len() {
+                    // Every class is placed, and the slots sum to the cards, so all are filled
+                    self.total += weight;
+                    for (row, counts) in self.expected.iter_mut().zip(&self.split) {
+                        for (e, &n) in row.iter_mut().zip(counts) {
+                            *e += weight * n as f64;
+                        }
+                    }
+                    return true;
+                }
+                if l == self.demand.len() {
+                    return left > 0 || self.run(k + 1, 0, self.supply.get(k + 1).copied().unwrap_or(0), weight);
+                }
+                let max = if self.hidden.classes[k][l] { left.min(self.demand[l]) } else { 0 };
+                let mut factorial = 1.0;
+                for n in 0..=max {
+                    if n > 0 {
+                        factorial *= n as f64;
+                    }
+                    self.demand[l] -= n;
+                    self.split[k][l] = n;
+                    let ok = self.run(k, l + 1, left - n, weight / factorial);
+                    self.split[k][l] = 0;
+                    self.demand[l] += n;
+                    if !ok {
+                        return false;
+                    }
+                }
+                true
+            }
+        }
+
+        let (nk, nl) = (supply.len(), demand.len());
+        let first = supply.first().copied().unwrap_or(0);
+        let mut search = Search {
+            hidden: self,
+            supply,
+            demand,
+            split: vec![vec![0; nl]; nk],
+            total: 0.0,
+            expected: vec![vec![0.0; nl]; nk],
+            visited: 0,
+        };
+        if !search.run(0, 0, first, 1.0) || search.total == 0.0 {
+            return None;
+        }
+        let total = search.total;
+        Some(
+            search
+                .expected
+                .into_iter()
+                .map(|row| row.into_iter().map(|e| e / total).collect())
+                .collect(),
+        )
+    }
+
+    fn probabilities(&self, perspective: PlayerId, samples: usize, rng: &mut impl Rng) -> CardProbabilities {
+        let demand = self.demand();
+        let slots: Vec<(Location, usize)> = self.locations.iter().copied().zip(demand).collect();
+
+        let (exact, share): (bool, Vec<Vec<f64>>) = match self.expected_exact() {
+            // Cards of a class are interchangeable, so each gets an equal share of the class
+            Some(expected) => {
+                let supply = self.supply();
+                let share = self
+                    .cards
+                    .iter()
+                    .map(|&(_, _, k)| expected[k].iter().map(|e| e / supply[k] as f64).collect())
+                    .collect();
+                (true, share)
+            }
+            None => (false, self.estimate(samples, rng)),
+        };
+
+        let cards = self
+            .cards
+            .iter()
+            .zip(share)
+            .map(|(&(card, copies, _), share)| CardProbability {
+                card,
+                copies,
+                locations: self.locations.iter().copied().zip(share).collect(),
+            })
+            .collect();
+
+        CardProbabilities { perspective, slots, exact, samples: if exact { 0 } else { samples }, cards }
+    }
+
+    /// Share of each card's copies at each location, estimated from `samples` weighted deals so
+    /// that every consistent deal counts equally (see `sample_weighted`).
+    fn estimate(&self, samples: usize, rng: &mut impl Rng) -> Vec<Vec<f64>> {
+        let mut counts = vec![vec![0.0; self.locations.len()]; self.cards.len()];
+        let mut total = 0.0;
+        for _ in 0..samples {
+            let (dealt, weight) = self.sample_weighted(rng);
+            total += weight;
+            for (l, dealt) in dealt.iter().enumerate() {
+                for &c in dealt {
+                    counts[c][l] += weight;
+                }
+            }
+        }
+        counts
+            .iter()
+            .zip(&self.cards)
+            .map(|(row, &(_, copies, _))| row.iter().map(|&n| n / (total.max(1.0) * copies as f64)).collect())
+            .collect()
+    }
+
+    /// Randomly pick the unseen cards (indices into `cards`) dealt to each location, respecting voids.
+    /// Cards are placed one at a time, so with voids some consistent deals come up more often than
+    /// others. That is cheap and good enough for `determinize`; `sample_weighted` corrects for it.
+    fn sample(&self, rng: &mut impl Rng) -> Vec<Vec<usize>> {
+        self.sample_with(rng, false).0
+    }
+
+    /// A deal from `sample` with its importance weight: the inverse of the chance of placing the
+    /// cards where they went, given the order they were placed in. Averages weighted by it treat
+    /// every consistent deal as equally likely.
+    fn sample_weighted(&self, rng: &mut impl Rng) -> (Vec<Vec<usize>>, f64) {
+        self.sample_with(rng, true)
+    }
+
+    fn sample_with(&self, rng: &mut impl Rng, weigh: bool) -> (Vec<Vec<usize>>, f64) {
+        let mut pool: Vec<usize> = Vec::new();
+        for (c, &(_, n, _)) in self.cards.iter().enumerate() {
+            pool.extend(std::iter::repeat_n(c, n));
+        }
+        for i in (1..pool.len()).rev() {
+            let j = rng.gen_range(0..=i);
+            pool.swap(i, j);
+        }
+
+        let mut dealt: Vec<Vec<usize>> = vec![Vec::new(); self.locations.len()];
+        let mut weight = 1.0;
+        if self.classes.len() <= 1 {
+            // Any deal is consistent: fill the hidden positions in order
+            let mut pool = pool.into_iter();
+            for (l, positions) in self.positions.iter().enumerate() {
+                dealt[l].extend(pool.by_ref().take(positions.len()));
+            }
+        } else {
+            // Pick a location per card, weighted by its free slots, where the remaining cards still fit
+            let mut supply = self.supply();
+            let mut demand = self.demand();
+            for &c in &pool {
+                let k = self.cards[c].2;
+                supply[k] -= 1;
+                let mut options: Vec<usize> = (0..self.locations.len())
+                    .filter(|&l| demand[l] > 0 && self.classes[k][l])
+                    .collect();
+                while !options.is_empty() {
+                    let mut pick = rng.gen_range(0..options.iter().map(|&l| demand[l]).sum::<usize>());
+                    let i = options
+                        .iter()
+                        .position(|&l| {
+                            if pick < demand[l] {
+                                return true;
+                            }
+                            pick -= demand[l];
+                            false
+                        })
+                        .unwrap_or(0);
+                    let l = options.swap_remove(i);
+                    let mut d = demand.clone();
+                    d[l] -= 1;
+                    if self.feasible(&supply, &d, None) {
+                        if weigh {
+                            // The options tried before were infeasible; count the untried ones that fit
+                            let fits = |&&m: &&usize| {
+                                let mut d = demand.clone();
+                                d[m] -= 1;
+                                self.feasible(&supply, &d, None)
+                            };
+                            let free: usize = options.iter().filter(fits).map(|&m| demand[m]).sum();
+                            weight *= (demand[l] + free) as f64 / demand[l] as f64;
+                        }
+                        demand = d;
+                        dealt[l].push(c);
+                        break;
+                    }
+                }
+            }
+        }
+        (dealt, weight)
+    }
+
+    /// Deal the unseen cards randomly to the hidden positions of `state`, respecting voids.
+    fn deal(&self, state: &mut GameState, rng: &mut impl Rng) {
+        let dealt = self.sample(rng);
+        for (l, location) in self.locations.iter().enumerate() {
+            for (&pos, &c) in self.positions[l].iter().zip(&dealt[l]) {
+                let card = self.cards[c].0;
+                match location {
+                    Location::Hand { player } => state.hands[player.index()][pos] = card,
+                    Location::Stock => state.stock[pos] = card,
+                    Location::Discard => state.discard[pos] = card.as_public(),
+                }
+            }
+        }
+    }
+}
+
+impl GameState {
+    /// Deduce where the cards hidden from `perspective` can be.
+    pub fn deduce(&self, perspective: PlayerId) -> Deduction {
+        Hidden::new(self, perspective).deduce(perspective)
+    }
+
+    /// Reveal every unknown card in a hand whose location is proven from `perspective`
+    /// (as public cards). Unknown stock cards are only revealed if their order doesn't matter
+    /// (all of them are proven copies of the same card).
+    pub fn reveal_deduced(&mut self, perspective: PlayerId) {
+        let deduction = self.deduce(perspective);
+        for (i, hand) in self.hands.iter_mut().enumerate() {
+            let mut forced = deduction.forced_at(Location::Hand { player: PlayerId::from_index(i) });
+            // Cards already there in private (only visible to their holder) account for some of them
+            for held in hand.iter().filter(|c| c.is_private()) {
+                if let Some(j) = forced.iter().position(|c| c.matches(held)) {
+                    forced.swap_remove(j);
+                }
+            }
+            for card in hand.iter_mut().filter(|c| c.is_unknown()) {
+                match forced.pop() {
+                    Some(c) => *card = c,
+                    None => break,
+                }
+            }
+        }
+
+        let forced = deduction.forced_at(Location::Stock);
+        let unknown = self.stock.iter().filter(|c| c.is_unknown()).count();
+        if unknown > 0 && forced.len() == unknown && forced.iter().all(|c| c.matches(&forced[0])) {
+            for card in self.stock.iter_mut().filter(|c| c.is_unknown()) {
+                *card = forced[0];
+            }
+        }
+    }
+
+    /// Chance of each hidden place holding each card unseen by `perspective`, with every consistent
+    /// deal equally likely. Computed exactly when the deals are few enough to enumerate, otherwise
+    /// estimated from `samples` random deals, weighted to undo the bias of those `determinize` uses.
+    pub fn card_probabilities(&self, perspective: PlayerId, samples: usize, rng: &mut impl Rng) -> CardProbabilities {
+        Hidden::new(self, perspective).probabilities(perspective, samples, rng)
+    }
+
+    /// Create a determinized copy of this state from a player's perspective.
+    /// Uses the player's known information (Public + their Private cards).
+    /// Unknown cards are shuffled and reassigned randomly, respecting proven voids.
+    pub fn determinize(&self, perspective: PlayerId, rng: &mut impl Rng) -> Self {
+        let mut state = self.clone();
+        Hidden::new(self, perspective).deal(&mut state, rng);
+
+        // Make perspective's Private cards Public
+        for card in &mut state.hands[perspective.index()] {
+            if card.is_private() {
+                *card = card.as_public();
+            }
+        }
+
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::test_util::{card, pid, small_deck};
+    use crate::ManualPositionBuilder;
+
+    fn hand(player: usize) -> Location {
+        Location::Hand { player: pid(player) }
+    }
+
+    /// An eight-card deck (aces and kings) with three players, from P0's seat holding
+    /// AS and KS after the clubs were discarded: AD, KD, AH and KH are unseen, and
+    /// `stock_size` of them are in the stock (AH face up at the bottom).
+    fn position(stock_size: usize) -> GameState {
+        let config = small_deck(&[Rank::Ace, Rank::King], 3, 2);
+        ManualPositionBuilder::new(card("AH"), pid(0))
+            .hand(vec![card("AS"), card("KS")])
+            .hand_size(pid(1), 2)
+            .hand_size(pid(2), 2 - stock_size)
+            .stock_size(stock_size)
+            .discard(vec![card("AC"), card("KC")])
+            .build(config)
+            .unwrap()
+    }
+
+    fn no_hearts(player: usize) -> Void {
+        Void { player: pid(player), suit: Suit::Hearts, below: None }
+    }
+
+    #[test]
+    fn unproven_cards_may_be_anywhere() {
+        let deduction = position(0).deduce(pid(0));
+        assert_eq!(deduction.slots, vec![(hand(1), 2), (hand(2), 2)]);
+        assert_eq!(deduction.cards.len(), 4);
+        for c in &deduction.cards {
+            assert_eq!(c.possible, vec![hand(1), hand(2)]);
+            assert!(c.forced.is_empty());
+        }
+        assert!(!deduction.is_complete());
+    }
+
+    #[test]
+    fn voids_force_the_remaining_cards() {
+        let mut state = position(0);
+        state.voids.push(no_hearts(1));
+        let deduction = state.deduce(pid(0));
+        assert!(deduction.is_complete());
+        assert_eq!(deduction.forced_at(hand(1)), vec![card("AD"), card("KD")]);
+        assert_eq!(deduction.forced_at(hand(2)), vec![card("AH"), card("KH")]);
+    }
+
+    #[test]
+    fn partial_voids_only_exclude_lower_cards() {
+        let mut state = position(0);
+        state.voids.push(Void { below: Some(Rank::Ace), ..no_hearts(1) });
+        let deduction = state.deduce(pid(0));
+        assert_eq!(deduction.forced_at(hand(2)), vec![card("KH")]);
+        assert!(deduction.forced_at(hand(1)).is_empty());
+        assert!(!deduction.is_complete());
+    }
+
+    #[test]
+    fn hidden_stock_cards_are_deduced() {
+        let mut state = position(2);
+        state.voids.push(no_hearts(1));
+        let deduction = state.deduce(pid(0));
+        assert_eq!(deduction.slots, vec![(hand(1), 2), (Location::Stock, 1)]);
+        assert!(deduction.is_complete());
+        assert_eq!(deduction.forced_at(Location::Stock), vec![card("KH")]);
+
+        state.reveal_deduced(pid(0));
+        assert!(state.hands[1].iter().all(|c| c.is_public()));
+        assert!(state.stock.iter().any(|c| c.matches(&card("KH"))));
+    }
+
+    #[test]
+    fn unrecorded_discards_are_a_hidden_place() {
+        let mut state = position(0);
+        // KC was discarded without being recorded, and neither opponent has clubs
+        state.discard[1] = Card::Unknown;
+        state.voids.extend([1, 2].map(|p| Void { player: pid(p), suit: Suit::Clubs, below: None }));
+        let deduction = state.deduce(pid(0));
+        assert_eq!(deduction.slots, vec![(hand(1), 2), (hand(2), 2), (Location::Discard, 1)]);
+        assert_eq!(deduction.forced_at(Location::Discard), vec![card("KC")]);
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let probabilities = state.card_probabilities(pid(0), 100, &mut rng);
+        assert!(probabilities.exact);
+        let dealt = state.determinize(pid(0), &mut rng);
+        assert_eq!(dealt.discard[1], card("KC").as_public());
+    }
+
+    #[test]
+    fn contradicting_voids_are_dropped() {
+        let mut state = position(0);
+        state.voids.extend([no_hearts(1), no_hearts(2)]);
+        let deduction = state.deduce(pid(0));
+        assert!(deduction.cards.iter().all(|c| c.possible.len() == 2 && c.forced.is_empty()));
+    }
+
+    #[test]
+    fn exact_probabilities_share_the_copies() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let probabilities = position(0).card_probabilities(pid(0), 100, &mut rng);
+        assert!(probabilities.exact);
+        for c in &probabilities.cards {
+            assert_eq!(c.locations, vec![(hand(1), 0.5), (hand(2), 0.5)]);
+        }
+    }
+
+    #[test]
+    fn determinized_deals_respect_voids() {
+        let mut state = position(0);
+        state.voids.push(no_hearts(1));
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..20 {
+            let dealt = state.determinize(pid(0), &mut rng);
+            assert!(dealt.hands[1].iter().all(|c| !c.is_unknown() && c.suit() == Suit::Diamonds));
+            assert!(dealt.hands[2].iter().all(|c| !c.is_unknown() && c.suit() == Suit::Hearts));
+        }
+    }
+
+    #[test]
+    fn sampled_probabilities_treat_deals_equally() {
+        // Placing one card at a time favors some of the deals of these cards to P1, P2 and the discard
+        let mut state = position(0);
+        state.discard[1] = Card::Unknown;
+        state.voids.extend([no_hearts(1), Void { player: pid(2), suit: Suit::Diamonds, below: None }]);
+        let hidden = Hidden::new(&state, pid(0));
+        let exact = hidden.probabilities(pid(0), 0, &mut StdRng::seed_from_u64(0));
+        assert!(exact.exact);
+
+        let estimate = hidden.estimate(10_000, &mut StdRng::seed_from_u64(0));
+        for (c, row) in exact.cards.iter().zip(&estimate) {
+            for (&(_, p), &e) in c.locations.iter().zip(row) {
+                assert!((p - e).abs() < 0.02, "{}: {p} vs {e}", c.card);
+            }
+        }
+    }
+}
@@ -0,0 +1,472 @@
+//! Corrections to a manual-mode game, for fixing misclicks without replaying the game.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{unseen_cards, Card, EngineError, GameState, Phase, PlayerId, StateError};
+
+/// A place a card can be moved from or to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Zone {
+    Hand { player: PlayerId },
+    /// The top of the stock (the next card drawn)
+    Stock,
+    Discard,
+}
+
+/// A correction to the recorded game (see `GameState::edit`).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Edit {
+    /// A played card was recorded wrongly: replace it on the table or in the discard pile.
+    ReplacePlayed { card: Card, with: Card },
+    /// Set the number of cards in a hand, drawing unknown cards from the stock or putting them back.
+    SetHandSize { player: PlayerId, size: usize },
+    /// The player is seen holding `card`: it becomes public, taking an unknown slot if needed.
+    Reveal { player: PlayerId, card: Card },
+    /// The player drew the face-up trump card (the last card of the stock), also when it was recorded
+    /// in another hand.
+    DrewTrump { player: PlayerId },
+    /// Move a card between zones. An unknown card in `from` stands in for `card` if it isn't there.
+    Move { card: Card, from: Zone, to: Zone },
+}
+
+impl GameState {
+    /// Apply a correction, then re-validate the state and recompute who may attack.
+    /// On error the state is left unchanged.
+    pub fn edit(&mut self, edit: &Edit) -> Result<(), EngineError> {
+        let mut next = self.clone();
+        next.apply_edit(edit)?;
+        // Seats are looked up while recomputing the attackers
+        next.validate_seats()?;
+        next.refresh_derived();
+        next.validate()?;
+        *self = next;
+        Ok(())
+    }
+
+    fn apply_edit(&mut self, edit: &Edit) -> Result<(), EngineError> {
+        match edit {
+            Edit::ReplacePlayed { card, with } => {
+                if with.is_unknown() {
+                    return Err(EngineError::inconsistent(StateError::UnknownCard));
+                }
+                let played = self
+                    .table
+                    .iter_mut()
+                    .flat_map(|p| std::iter::once(&mut p.attack).chain(p.defense.as_mut()))
+                    .chain(self.discard.iter_mut())
+                    .find(|c| c.matches(card))
+                    .ok_or_else(|| EngineError::inconsistent(StateError::NotPlayed { card: *card }))?;
+                *played = with.as_public();
+            }
+            Edit::SetHandSize { player, size } => {
+                let player = player.validate(self.num_players())?;
+                let hand = &mut self.hands[player.index()];
+                while hand.len() < *size {
+                    let card = self
+                        .stock
+                        .pop()
+                        .ok_or_else(|| EngineError::inconsistent(StateError::StockExhausted))?;
+                    hand.push(card);
+                    self.voids.retain(|v| v.player != player);
+                }
+                while self.hands[player.index()].len() > *size {
+                    let hand = &mut self.hands[player.index()];
+                    let i = hand
+                        .iter()
+                        .rposition(|c| c.is_unknown())
+                        .ok_or_else(|| EngineError::inconsistent(StateError::NoUnknownCard { player }))?;
+                    hand.remove(i);
+                    self.push_stock(Card::Unknown)?;
+                }
+            }
+            Edit::Reveal { player, card } => {
+                let player = player.validate(self.num_players())?;
+                if card.is_unknown() {
+                    return Err(EngineError::inconsistent(StateError::UnknownCard));
+                }
+                self.reveal(player, card)?;
+            }
+            Edit::DrewTrump { player } => {
+                let player = player.validate(self.num_players())?;
+                let card = self
+                    .trump_card
+                    .ok_or_else(|| EngineError::inconsistent(StateError::TrumpCardUnknown))?;
+                if self.stock.first().is_some_and(|c| c.matches(&card)) {
+                    return Err(EngineError::inconsistent(StateError::TrumpCardInStock { card }));
+                }
+                // The trump was recorded in another hand: that player drew an unknown card instead
+                if !self.hands[player.index()].iter().any(|c| c.matches(&card))
+                    && let Some(slot) = self
+                        .hands
+                        .iter_mut()
+                        .enumerate()
+                        .filter(|(i, _)| *i != player.index())
+                        .find_map(|(_, hand)| hand.iter_mut().find(|c| c.matches(&card)))
+                {
+                    *slot = Card::Unknown;
+                }
+                self.reveal(player, &card)?;
+            }
+            Edit::Move { card, from, to } => {
+                if card.is_unknown() {
+                    return Err(EngineError::inconsistent(StateError::UnknownCard));
+                }
+                let cards = self.zone_mut(*from)?;
+                // The face-up trump only leaves the stock as its last card
+                let first = usize::from(*from == Zone::Stock && cards.len() > 1);
+                let i = match cards[first..].iter().rposition(|c| c.matches(card)) {
+                    Some(i) => i + first,
+                    None if first == 1 && cards[0].matches(card) => {
+                        return Err(EngineError::inconsistent(StateError::TrumpCardInStock { card: *card }));
+                    }
+                    None => cards[first..]
+                        .iter()
+                        .rposition(|c| c.is_unknown())
+                        .map(|i| i + first)
+                        .ok_or_else(|| EngineError::inconsistent(StateError::NotInZone { card: *card, zone: *from }))?,
+                };
+                let moved = cards.remove(i);
+                let moved = if moved.is_unknown() { card.as_public() } else { moved };
+                let moved = match to {
+                    Zone::Discard => moved.as_public(),
+                    _ => moved,
+                };
+                match to {
+                    Zone::Stock => self.push_stock(moved)?,
+                    _ => self.zone_mut(*to)?.push(moved),
+                }
+                if let Zone::Hand { player } = to {
+                    self.voids.retain(|v| !(v.player == *player && v.excludes(card)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Put a card on top of the stock. A card put back on an empty stock becomes its bottom card,
+    /// so it has to be the face-up trump.
+    fn push_stock(&mut self, card: Card) -> Result<(), EngineError> {
+        if self.stock.is_empty() && !self.trump_card.is_some_and(|t| card.matches(&t)) {
+            return Err(EngineError::inconsistent(StateError::BottomCardNotTrump));
+        }
+        self.stock.push(card);
+        Ok(())
+    }
+
+    /// Make `card` public in the player's hand, in place of an unknown card unless it is already there.
+    fn reveal(&mut self, player: PlayerId, card: &Card) -> Result<(), EngineError> {
+        let hand = &self.hands[player.index()];
+        let i = match hand.iter().position(|c| c.matches(card)) {
+            Some(i) => i,
+            None => {
+                if !self.unlocated_cards().iter().any(|c| c.matches(card)) {
+                    return Err(EngineError::inconsistent(StateError::AlreadyLocated { card: *card }));
+                }
+                hand.iter()
+                    .position(|c| c.is_unknown())
+                    .ok_or(EngineError::CardNotInHand { player, card: *card })?
+            }
+        };
+        self.hands[player.index()][i] = card.as_public();
+        // A seen card overrides what was deduced about the hand
+        self.voids.retain(|v| !(v.player == player && v.excludes(card)));
+        Ok(())
+    }
+
+    /// Cards of the deck that are not recorded anywhere (all unknown slots together).
+    fn unlocated_cards(&self) -> Vec<Card> {
+        let known: Vec<Card> = self
+            .hands
+            .iter()
+            .flatten()
+            .chain(&self.stock)
+            .chain(&self.discard)
+            .chain(self.table.iter().flat_map(|p| std::iter::once(&p.attack).chain(p.defense.as_ref())))
+            .copied()
+            .collect();
+        self.unseen(&known)
+    }
+
+    fn zone_mut(&mut self, zone: Zone) -> Result<&mut Vec<Card>, EngineError> {
+        Ok(match zone {
+            Zone::Hand { player } => {
+                let player = player.validate(self.num_players())?;
+                &mut self.hands[player.index()]
+            }
+            Zone::Stock => &mut self.stock,
+            Zone::Discard => &mut self.discard,
+        })
+    }
+
+    /// Recompute the attackers of the current trick, keeping the turn with the same attacker if possible.
+    fn refresh_derived(&mut self) {
+        let current = self.current_attacker();
+        // Attackers who went out during this trick stay listed until it ends
+        let mut attackers = self.build_attackers(self.attacker);
+        for &p in &self.attackers {
+            if self.hand_size(p) == 0 && !attackers.contains(&p) {
+                attackers.push(p);
+            }
+        }
+        let n = self.num_players();
+        attackers.sort_by_key(|p| (p.index() + n - self.attacker.index()) % n);
+        self.attackers = attackers;
+        self.current_attacker_idx = self.attackers.iter().position(|&p| p == current).unwrap_or(0);
+        self.throw_start_idx = self.throw_start_idx.min(self.attackers.len().saturating_sub(1));
+        let attackers = &self.attackers;
+        self.done_attackers.retain(|p| attackers.contains(p));
+        self.record_finishers();
+    }
+
+    /// Fill in `trump_card` for saves from before it was recorded: while the stock lasts, it is
+    /// the face-up card at the bottom.
+    pub fn restore_trump_card(&mut self) {
+        if self.trump_card.is_none() {
+            self.trump_card = self.stock.first().filter(|c| c.is_public() && c.suit() == self.trump).copied();
+        }
+    }
+
+    /// Check that the state is consistent: every card of the deck is somewhere exactly once,
+    /// and the current trick is playable.
+    pub fn validate(&self) -> Result<(), EngineError> {
+        self.config.validate()?;
+        self.validate_seats()?;
+
+        let table: Vec<Card> = self.table.iter().flat_map(|p| std::iter::once(p.attack).chain(p.defense)).collect();
+        let all: Vec<&Card> = self.hands.iter().flatten().chain(&self.stock).chain(&self.discard).chain(&table).collect();
+        let total = self.config.total_cards()?;
+        if all.len() != total {
+            return Err(EngineError::inconsistent(StateError::WrongCardCount { cards: all.len(), deck: total }));
+        }
+        let spec = self.config.deck_spec()?;
+        let known: Vec<Card> = all.iter().filter(|c| !c.is_unknown()).map(|c| **c).collect();
+        if let Some(card) = known.iter().find(|c| !spec.contains(c)) {
+            return Err(EngineError::inconsistent(StateError::CardNotInDeck { card: *card }));
+        }
+        if unseen_cards(&self.config, &known)?.len() + known.len() != total {
+            return Err(EngineError::inconsistent(StateError::TooManyCopies));
+        }
+        if table.iter().any(|c| c.is_unknown()) {
+            return Err(EngineError::inconsistent(StateError::UnknownTableCard));
+        }
+        if let (Some(bottom), Some(trump_card)) = (self.stock.first(), self.trump_card)
+            && !bottom.matches(&trump_card)
+        {
+            return Err(EngineError::inconsistent(StateError::BottomCardNotTrump));
+        }
+
+        for (i, pile) in self.table.iter().enumerate() {
+            if let Some(defense) = pile.defense
+                && !defense.beats(&pile.attack, self.trump)
+            {
+                let reason = StateError::DefenseDoesNotBeat { pile: i, card: defense, attack: pile.attack };
+                return Err(EngineError::inconsistent(reason));
+            }
+        }
+        let open = self.open_pile_index().is_some();
+        match self.phase {
+            Phase::Attacking if open => {
+                return Err(EngineError::inconsistent(StateError::UndefendedWhileAttacking));
+            }
+            Phase::Defending if !open => return Err(EngineError::inconsistent(StateError::NoOpenPile)),
+            Phase::Throwing if self.table.is_empty() => {
+                return Err(EngineError::inconsistent(StateError::NothingToTake));
+            }
+            _ => {}
+        }
+        if !self.is_terminal() {
+            if self.attacker == self.defender {
+                return Err(EngineError::inconsistent(StateError::AttackerDefends));
+            }
+            if self.phase == Phase::Defending && self.undefended_pile_count() > self.hand_size(self.defender) {
+                return Err(EngineError::inconsistent(StateError::DefenderShortOfCards));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that there is a hand for every player and every seat refers to one of them.
+    fn validate_seats(&self) -> Result<(), EngineError> {
+        let n = self.num_players();
+        if n != self.config.num_players {
+            return Err(EngineError::inconsistent(StateError::WrongHandCount { hands: n, num_players: self.config.num_players }));
+        }
+        let seats = [self.attacker, self.defender, self.original_attacker, self.last_played_attacker];
+        for pid in seats
+            .iter()
+            .chain(&self.attackers)
+            .chain(&self.done_attackers)
+            .chain(self.finished.iter().flatten())
+            .chain(self.voids.iter().map(|v| &v.player))
+        {
+            pid.validate(n)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{card, pid};
+    use crate::{Action, GameConfig};
+
+    /// A three-player manual game from P0's seat, after P0 attacked with 6S and P1 beat it with QS.
+    fn game() -> GameState {
+        let config = GameConfig { num_players: 3, ..GameConfig::default() };
+        let hand = ["6S", "7S", "8S", "9S", "TS", "JS"].map(card).to_vec();
+        let mut state = GameState::new_manual_game(card("6H"), hand, 0, 0, vec![], config).unwrap();
+        state.apply(&Action::Attack { card: card("6S") }).unwrap();
+        state.apply(&Action::Defend { pile_index: 0, card: card("QS") }).unwrap();
+        state
+    }
+
+    #[test]
+    fn replace_played_card() {
+        let mut state = game();
+        state.edit(&Edit::ReplacePlayed { card: card("QS"), with: card("KS") }).unwrap();
+        assert_eq!(state.table[0].defense, Some(card("KS")));
+
+        // The replacement must still beat the attack
+        let err = state.edit(&Edit::ReplacePlayed { card: card("KS"), with: card("7C") }).unwrap_err();
+        let reason = StateError::DefenseDoesNotBeat { pile: 0, card: card("7C"), attack: card("6S") };
+        assert_eq!(err, EngineError::InvalidState { reason });
+        assert_eq!(state.table[0].defense, Some(card("KS")));
+    }
+
+    #[test]
+    fn revealed_cards_are_located_once() {
+        let mut state = game();
+        state.edit(&Edit::Reveal { player: pid(2), card: card("AH") }).unwrap();
+        assert!(state.hands[2].contains(&card("AH")));
+        assert!(state.edit(&Edit::Reveal { player: pid(1), card: card("AH") }).is_err());
+        // Your own cards are not somewhere else
+        assert!(state.edit(&Edit::Reveal { player: pid(1), card: card("7S") }).is_err());
+    }
+
+    #[test]
+    fn hand_sizes_and_moves_keep_the_deck_complete() {
+        let mut state = game();
+        let stock = state.stock.len();
+        state.edit(&Edit::SetHandSize { player: pid(2), size: 8 }).unwrap();
+        assert_eq!((state.hand_size(pid(2)), state.stock.len()), (8, stock - 2));
+        state.edit(&Edit::SetHandSize { player: pid(2), size: 5 }).unwrap();
+        assert_eq!((state.hand_size(pid(2)), state.stock.len()), (5, stock + 1));
+
+        state.edit(&Edit::Move { card: card("AC"), from: Zone::Stock, to: Zone::Discard }).unwrap();
+        state.edit(&Edit::Move { card: card("AC"), from: Zone::Discard, to: Zone::Hand { player: pid(1) } }).unwrap();
+        assert!(state.hands[1].contains(&card("AC")));
+        assert!(state.discard.is_empty());
+        state.validate().unwrap();
+
+        // Nothing is left in the discard pile to move
+        let err = state.edit(&Edit::Move { card: card("AD"), from: Zone::Discard, to: Zone::Stock }).unwrap_err();
+        assert_eq!(err, EngineError::InvalidState { reason: StateError::NotInZone { card: card("AD"), zone: Zone::Discard } });
+    }
+
+    #[test]
+    fn face_up_trump_is_drawn_last() {
+        let mut state = game();
+        let err = state.edit(&Edit::DrewTrump { player: pid(1) }).unwrap_err();
+        assert_eq!(err, EngineError::InvalidState { reason: StateError::TrumpCardInStock { card: card("6H") } });
+
+        // Not even by moving it while other cards are still above it
+        let err = state.edit(&Edit::Move { card: card("6H"), from: Zone::Stock, to: Zone::Hand { player: pid(1) } });
+        assert_eq!(err, Err(EngineError::InvalidState { reason: StateError::TrumpCardInStock { card: card("6H") } }));
+        assert_eq!(state.stock[0], card("6H"));
+
+        let mut broken = state.clone();
+        broken.stock.swap(0, 1);
+        assert_eq!(broken.validate(), Err(EngineError::InvalidState { reason: StateError::BottomCardNotTrump }));
+    }
+
+    #[test]
+    fn out_of_range_seats_are_rejected() {
+        let mut state = game();
+        state.attacker = pid(5);
+        let err = state.clone().edit(&Edit::SetHandSize { player: pid(1), size: 6 }).unwrap_err();
+        assert_eq!(err, EngineError::InvalidSeat { seat: 5, num_players: 3 });
+
+        let mut state = game();
+        state.attackers.push(pid(3));
+        assert!(matches!(state.validate(), Err(EngineError::InvalidSeat { seat: 3, .. })));
+
+        let mut state = game();
+        state.finished = vec![vec![pid(7)]];
+        assert!(matches!(state.validate(), Err(EngineError::InvalidSeat { seat: 7, .. })));
+    }
+
+    #[test]
+    fn phase_must_match_the_table() {
+        let mut state = game();
+        state.phase = Phase::Defending;
+        assert_eq!(state.validate(), Err(EngineError::InvalidState { reason: StateError::NoOpenPile }));
+
+        let mut state = game();
+        state.table[0].defense = None;
+        state.hands[1].push(Card::Unknown);
+        assert_eq!(state.validate(), Err(EngineError::InvalidState { reason: StateError::UndefendedWhileAttacking }));
+
+        let mut state = game();
+        state.discard.extend(state.table.drain(..).flat_map(|p| [p.attack, p.defense.unwrap()]));
+        state.phase = Phase::Throwing;
+        assert_eq!(state.validate(), Err(EngineError::InvalidState { reason: StateError::NothingToTake }));
+    }
+
+    #[test]
+    fn bottom_of_the_stock_stays_the_trump_card() {
+        let mut state = game();
+        let stock = state.stock.len();
+        // P2 draws the whole stock, the face-up 6H last
+        state.edit(&Edit::SetHandSize { player: pid(2), size: 6 + stock }).unwrap();
+        assert!(state.stock.is_empty());
+        assert!(state.hands[2].contains(&card("6H")));
+
+        // An unknown card put back would end up at the bottom
+        let err = state.edit(&Edit::SetHandSize { player: pid(2), size: 5 + stock }).unwrap_err();
+        assert_eq!(err, EngineError::InvalidState { reason: StateError::BottomCardNotTrump });
+        let err = state.edit(&Edit::Move { card: card("AC"), from: Zone::Hand { player: pid(2) }, to: Zone::Stock });
+        assert_eq!(err, Err(EngineError::InvalidState { reason: StateError::BottomCardNotTrump }));
+
+        // The trump card itself may go back
+        state.edit(&Edit::Move { card: card("6H"), from: Zone::Hand { player: pid(2) }, to: Zone::Stock }).unwrap();
+        assert_eq!(state.stock, vec![card("6H")]);
+        state.edit(&Edit::SetHandSize { player: pid(2), size: 4 + stock }).unwrap();
+        assert_eq!(state.stock[0], card("6H"));
+    }
+
+    #[test]
+    fn drawn_trump_moves_to_the_player_who_drew_it() {
+        let mut state = game();
+        let stock = state.stock.len();
+        // Drawing the stock recorded the face-up 6H in P2's hand
+        state.edit(&Edit::SetHandSize { player: pid(2), size: 6 + stock }).unwrap();
+        assert!(state.hands[2].contains(&card("6H")));
+
+        state.edit(&Edit::DrewTrump { player: pid(1) }).unwrap();
+        assert!(state.hands[1].contains(&card("6H")));
+        assert!(!state.hands[2].contains(&card("6H")));
+        assert_eq!((state.hand_size(pid(1)), state.hand_size(pid(2))), (5, 6 + stock));
+        // Recording it again changes nothing
+        let before = state.clone();
+        state.edit(&Edit::DrewTrump { player: pid(1) }).unwrap();
+        assert_eq!(state, before);
+    }
+
+    #[test]
+    fn old_saves_get_the_trump_card_from_the_stock() {
+        let mut state = game();
+        state.trump_card = None;
+        state.restore_trump_card();
+        assert_eq!(state.trump_card, Some(card("6H")));
+
+        state.edit(&Edit::SetHandSize { player: pid(2), size: 6 + state.stock.len() }).unwrap();
+        state.trump_card = None;
+        state.restore_trump_card();
+        assert_eq!(state.trump_card, None);
+    }
+}
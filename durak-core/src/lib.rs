@@ -8,7 +8,24 @@ use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::hash::Hash;
 
+mod deduction;
+mod edit;
+mod position;
+mod search;
+mod series;
+#[cfg(test)]
+pub(crate) mod test_util;
+mod tracker;
+
+pub use deduction::{CardDeduction, CardProbabilities, CardProbability, Deduction, Location, Void};
+pub use edit::{Edit, Zone};
+pub use position::ManualPositionBuilder;
+pub use search::{SearchSettings, SearchTree};
+pub use series::{Match, MatchConfig, MatchScoring, StartRule};
+pub use tracker::{SuitCount, Tracker};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
 pub enum Suit {
     Clubs,
     Diamonds,
@@ -17,6 +34,7 @@ pub enum Suit {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
 #[repr(u8)]
 pub enum Rank {
     Two = 2,
@@ -32,10 +50,14 @@ pub enum Rank {
     Queen = 12,
     King = 13,
     Ace = 14,
+    /// A joker beats every card, including trumps, and cannot be beaten.
+    /// Its suit only tells red (hearts) and black (spades) jokers apart.
+    Joker = 15,
 }
 
 /// A card with visibility - who knows what this card is.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum Card {
     /// Everyone knows this card (e.g., played cards, trump card).
@@ -81,11 +103,26 @@ impl Card {
         matches!(self, Card::Unknown)
     }
 
+    pub fn is_joker(&self) -> bool {
+        !self.is_unknown() && self.rank() == Rank::Joker
+    }
+
+    /// Check if this is a card of the trump suit (jokers are not).
+    pub fn is_trump(&self, trump: Suit) -> bool {
+        !self.is_joker() && self.suit() == trump
+    }
+
     pub fn beats(&self, other: &Card, trump: Suit) -> bool {
+        if other.is_joker() {
+            return false;
+        }
+        if self.is_joker() {
+            return true;
+        }
         if self.suit() == other.suit() {
             return self.rank() > other.rank();
         }
-        self.suit() == trump && other.suit() != trump
+        self.is_trump(trump) && !other.is_trump(trump)
     }
 
     /// Check if this card matches another card (same suit and rank, ignoring visibility).
@@ -107,39 +144,119 @@ impl Card {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
-pub enum PlayerId {
-    #[default]
-    P0,
-    P1,
-    P2,
-    P3,
-    P4,
-    P5,
+/// Short notation: rank (`2`-`9`, `T`, `J`, `Q`, `K`, `A`) then suit (`C`, `D`, `H`, `S`),
+/// e.g. `6H` or `TS`; `RJ` and `BJ` are the red and black joker, `??` an unknown card.
+impl std::fmt::Display for Card {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_unknown() {
+            return f.write_str("??");
+        }
+        if self.is_joker() {
+            return f.write_str(if self.suit() == Suit::Hearts { "RJ" } else { "BJ" });
+        }
+        let rank = match self.rank() {
+            Rank::Ten => 'T',
+            Rank::Jack => 'J',
+            Rank::Queen => 'Q',
+            Rank::King => 'K',
+            Rank::Ace => 'A',
+            r => char::from(b'0' + r as u8),
+        };
+        let suit = match self.suit() {
+            Suit::Clubs => 'C',
+            Suit::Diamonds => 'D',
+            Suit::Hearts => 'H',
+            Suit::Spades => 'S',
+        };
+        write!(f, "{rank}{suit}")
+    }
 }
 
-impl PlayerId {
-    pub fn from_index(i: usize) -> Self {
-        match i {
-            0 => PlayerId::P0,
-            1 => PlayerId::P1,
-            2 => PlayerId::P2,
-            3 => PlayerId::P3,
-            4 => PlayerId::P4,
-            _ => PlayerId::P5,
+/// Parses the short notation of `Display` into a public card (case-insensitive).
+impl std::str::FromStr for Card {
+    type Err = EngineError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || EngineError::InvalidNotation { notation: s.to_string() };
+        let upper = s.trim().to_ascii_uppercase();
+        match upper.as_str() {
+            "??" => return Ok(Card::Unknown),
+            "RJ" => return Ok(Card::public(Suit::Hearts, Rank::Joker)),
+            "BJ" => return Ok(Card::public(Suit::Spades, Rank::Joker)),
+            _ => {}
         }
+        let (rank, suit) = upper.split_at_checked(upper.len().saturating_sub(1)).ok_or_else(invalid)?;
+        let rank = match rank {
+            "2" => Rank::Two,
+            "3" => Rank::Three,
+            "4" => Rank::Four,
+            "5" => Rank::Five,
+            "6" => Rank::Six,
+            "7" => Rank::Seven,
+            "8" => Rank::Eight,
+            "9" => Rank::Nine,
+            "T" | "10" => Rank::Ten,
+            "J" => Rank::Jack,
+            "Q" => Rank::Queen,
+            "K" => Rank::King,
+            "A" => Rank::Ace,
+            _ => return Err(invalid()),
+        };
+        let suit = match suit {
+            "C" => Suit::Clubs,
+            "D" => Suit::Diamonds,
+            "H" => Suit::Hearts,
+            "S" => Suit::Spades,
+            _ => return Err(invalid()),
+        };
+        Ok(Card::public(suit, rank))
     }
+}
 
-    pub fn other(self) -> PlayerId {
-        match self {
-            PlayerId::P0 => PlayerId::P1,
-            PlayerId::P1 => PlayerId::P0,
-            _ => PlayerId::P0,
+/// TypeScript declaration of `PlayerId`, which has its own serde format.
+#[cfg(feature = "ts")]
+#[wasm_bindgen::prelude::wasm_bindgen(typescript_custom_section)]
+const TS_PLAYER_ID: &str = r#"
+/** Seat of a player, counted clockwise from 0 (integers are accepted as input as well). */
+export type PlayerId = `P${number}`;
+"#;
+
+/// Seat of a player, counted clockwise from 0.
+/// Serialized as `"P0"`, `"P1"`, ... (the format of saved games); integers are accepted as well.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct PlayerId(u8);
+
+impl PlayerId {
+    /// Seat `i` at a table of `num_players`, rejecting seats that don't exist.
+    pub fn new(i: usize, num_players: usize) -> Result<Self, EngineError> {
+        if i >= num_players || i > u8::MAX as usize {
+            return Err(EngineError::InvalidSeat { seat: i, num_players });
         }
+        Ok(PlayerId(i as u8))
+    }
+
+    /// Seat `i` without a table size check (e.g. when iterating `0..num_players`).
+    /// Panics if `i` does not fit a seat number.
+    pub fn from_index(i: usize) -> Self {
+        PlayerId(u8::try_from(i).expect("seat index out of range"))
+    }
+
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    /// Check that this seat exists at a table of `num_players`.
+    pub fn validate(self, num_players: usize) -> Result<Self, EngineError> {
+        PlayerId::new(self.index(), num_players)
     }
 
     pub fn next(self, num_players: usize) -> PlayerId {
-        PlayerId::from_index((self as usize + 1) % num_players)
+        PlayerId::from_index((self.index() + 1) % num_players)
+    }
+
+    /// The player to the right (counter-clockwise).
+    pub fn prev(self, num_players: usize) -> PlayerId {
+        PlayerId::from_index((self.index() + num_players - 1) % num_players)
     }
 
     /// Get next player, skipping players with empty hands (who are out of the game).
@@ -147,7 +264,7 @@ impl PlayerId {
         let mut next = self.next(num_players);
         let start = next;
         loop {
-            if hands[next as usize].len() > 0 {
+            if !hands[next.index()].is_empty() {
                 return next;
             }
             next = next.next(num_players);
@@ -159,13 +276,68 @@ impl PlayerId {
     }
 }
 
+impl std::fmt::Debug for PlayerId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "P{}", self.0)
+    }
+}
+
+impl std::fmt::Display for PlayerId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "P{}", self.0)
+    }
+}
+
+impl Serialize for PlayerId {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for PlayerId {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct PlayerIdVisitor;
+
+        impl serde::de::Visitor<'_> for PlayerIdVisitor {
+            type Value = PlayerId;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str("a seat like \"P0\" or a seat number")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<PlayerId, E> {
+                v.strip_prefix('P')
+                    .and_then(|n| n.parse::<u8>().ok())
+                    .map(PlayerId)
+                    .ok_or_else(|| E::invalid_value(serde::de::Unexpected::Str(v), &self))
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, v: u64) -> Result<PlayerId, E> {
+                u8::try_from(v)
+                    .map(PlayerId)
+                    .map_err(|_| E::invalid_value(serde::de::Unexpected::Unsigned(v), &self))
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, v: i64) -> Result<PlayerId, E> {
+                u8::try_from(v)
+                    .map(PlayerId)
+                    .map_err(|_| E::invalid_value(serde::de::Unexpected::Signed(v), &self))
+            }
+        }
+
+        deserializer.deserialize_any(PlayerIdVisitor)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
 pub struct Pile {
     pub attack: Card,
     pub defense: Option<Card>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
 pub enum Phase {
     /// Attacker may add a card or end attack.
     Attacking,
@@ -177,6 +349,7 @@ pub enum Phase {
 
 /// Player type for AI behavior configuration.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
 pub enum PlayerType {
     /// Human player - manual input, optional MCTS hints.
     Human,
@@ -186,42 +359,549 @@ pub enum PlayerType {
     MCTS,
 }
 
+/// Which players may join the attack and throw in cards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
+#[serde(rename_all = "snake_case")]
+pub enum ThrowIn {
+    /// Every active player except the defender.
+    #[default]
+    Everyone,
+    /// Only the players seated directly left and right of the defender.
+    Neighbours,
+    /// Only the main attacker.
+    MainAttacker,
+}
+
+/// Where a reflected attack is passed on to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
+#[serde(rename_all = "snake_case")]
+pub enum ReflectDirection {
+    /// To the next active opponent clockwise (the usual rule).
+    #[default]
+    Clockwise,
+    /// To the previous active opponent (counter-clockwise).
+    CounterClockwise,
+    /// The defender chooses either neighbour (reflect actions name the target).
+    Either,
+}
+
+/// How attackers take turns adding cards to a trick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
+#[serde(rename_all = "snake_case")]
+pub enum TurnModel {
+    /// Attackers add cards one after another and each must pass in turn.
+    #[default]
+    Sequential,
+    /// Real-time play: every eligible attacker may add cards at any moment (also while the
+    /// defender is still defending), applied in arrival order through `GameState::apply_as`.
+    /// Passing is a "done" vote; the trick ends once every attacker is done, and a new card
+    /// reopens the vote. `legal_actions` and `apply` still offer the trick as sequential moves
+    /// (the first attacker who is not done is to move), so search can treat it like `Sequential`.
+    Simultaneous,
+}
+
+/// Which cards a joker matches when attacking or throwing in on a trick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
+#[serde(rename_all = "snake_case")]
+pub enum JokerMatching {
+    /// Jokers are a rank of their own: they can only join a trick that already has a joker.
+    #[default]
+    Jokers,
+    /// Jokers are wild: they can be thrown in on any trick.
+    Any,
+}
+
+/// What an opponent in a manual game showed of their trumps before the first trick.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TrumpDeclaration {
+    /// Their lowest trump: they hold it and no lower one.
+    Lowest { rank: Rank },
+    /// They hold no trump.
+    NoTrump,
+    /// Nothing was declared.
+    Undeclared,
+}
+
+/// House rule applied at the end of the game.
+/// Once the players left may not go out with any of their cards, the game ends in a draw.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EndRule {
+    /// Players may not go out by playing a trump as their last card. Jokers count as trumps here.
+    NoFinishWithTrump,
+    /// Players may not go out by playing a card of this rank as their last card.
+    NoFinishWithRank { rank: Rank },
+    /// Losing with (at least) a pair of this rank in hand is a special humiliation
+    /// ("pogony" or epaulettes, classically a pair of sixes).
+    Epaulettes { rank: Rank },
+}
+
+impl EndRule {
+    /// Check if this rule forbids going out with the given card.
+    fn forbids_finish(&self, card: &Card, trump: Suit) -> bool {
+        match self {
+            EndRule::NoFinishWithTrump => card.is_trump(trump) || card.is_joker(),
+            EndRule::NoFinishWithRank { rank } => card.rank() == *rank,
+            EndRule::Epaulettes { .. } => false,
+        }
+    }
+}
+
+/// Composition of a single deck: every listed rank in each of the four suits, plus jokers.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
+pub struct DeckSpec {
+    /// Ranks present in every suit
+    pub ranks: Vec<Rank>,
+    /// Number of jokers added to the deck
+    #[serde(default)]
+    pub jokers: usize,
+}
+
+impl DeckSpec {
+    /// The usual deck of `size` cards (32, 36, 40, 44, 48 or 52), dropping the lowest ranks.
+    pub fn standard(size: usize) -> Result<Self, EngineError> {
+        let ranks = match size {
+            32 => Rank::Seven,
+            36 => Rank::Six,
+            40 => Rank::Five,
+            44 => Rank::Four,
+            48 => Rank::Three,
+            52 => Rank::Two,
+            _ => {
+                return Err(EngineError::setup(SetupError::UnsupportedDeckSize { size }));
+            }
+        };
+        Ok(Self {
+            ranks: ALL_RANKS.iter().copied().filter(|&r| r >= ranks).collect(),
+            jokers: 0,
+        })
+    }
+
+    /// Check that the deck can be played with.
+    pub fn validate(&self) -> Result<(), EngineError> {
+        if self.ranks.is_empty() {
+            return Err(EngineError::setup(SetupError::NoRanks));
+        }
+        if self.ranks.iter().collect::<HashSet<_>>().len() != self.ranks.len() {
+            return Err(EngineError::setup(SetupError::RepeatedRank));
+        }
+        if self.ranks.contains(&Rank::Joker) {
+            return Err(EngineError::setup(SetupError::JokerRank));
+        }
+        Ok(())
+    }
+
+    /// Number of cards in the deck.
+    pub fn len(&self) -> usize {
+        self.ranks.len() * ALL_SUITS.len() + self.jokers
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether this deck contains the given card.
+    pub fn contains(&self, card: &Card) -> bool {
+        if card.is_joker() {
+            return self.cards().iter().any(|c| c.matches(card));
+        }
+        !card.is_unknown() && self.ranks.contains(&card.rank())
+    }
+
+    /// All cards of the deck (private), ordered by suit then by the listed ranks,
+    /// followed by the jokers (alternating red and black).
+    pub fn cards(&self) -> Vec<Card> {
+        let mut out = Vec::with_capacity(self.len());
+        for s in ALL_SUITS {
+            for r in &self.ranks {
+                out.push(Card::private(s, *r));
+            }
+        }
+        for i in 0..self.jokers {
+            let color = if i % 2 == 0 { Suit::Hearts } else { Suit::Spades };
+            out.push(Card::private(color, Rank::Joker));
+        }
+        out
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
 pub struct GameConfig {
-    /// Number of cards in the deck (32, 36, 40, 44, 48, or 52)
+    /// Number of cards in a standard deck (32, 36, 40, 44, 48, or 52), ignored with `custom_deck`
     pub deck_size: usize,
-    /// Number of players (2-6)
+    /// Explicit deck composition, replacing the standard `deck_size` deck
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custom_deck: Option<DeckSpec>,
+    /// Which cards jokers (from `custom_deck`) match when attacking or throwing in
+    #[serde(default)]
+    pub joker_matching: JokerMatching,
+    /// Number of decks shuffled together (duplicate cards are possible with more than one)
+    #[serde(default = "default_decks")]
+    pub decks: usize,
+    /// Number of players (2-8)
     pub num_players: usize,
     /// Trump reflecting: if true, a trump can be reflected back to attacker
     pub trump_reflecting: bool,
     /// Reflecting: if true, defender can reflect an attack with a card of same rank
     pub reflecting: bool,
+    /// Number of cards dealt to each player, hands are refilled up to this size
+    #[serde(default = "default_hand_size")]
+    pub hand_size: usize,
+    /// Maximum number of piles in a trick (None: only limited by the defender's hand)
+    #[serde(default)]
+    pub max_piles: Option<usize>,
+    /// Maximum number of piles in the first trick of the game (commonly 5)
+    #[serde(default)]
+    pub first_trick_limit: Option<usize>,
+    /// Who may join the attack and throw in
+    #[serde(default)]
+    pub throw_in: ThrowIn,
+    /// Multi-card play: if true, attackers may lay down several cards at once
+    #[serde(default)]
+    pub multi_card_attacks: bool,
+    /// Trump exchange: if true, the holder of the lowest trump may swap it for the face-up trump
+    #[serde(default)]
+    pub trump_exchange: bool,
+    /// Team per seat (empty: everyone plays for themselves).
+    /// Teammates never attack each other, and a team loses if one of its members ends as durak.
+    #[serde(default)]
+    pub teams: Vec<u8>,
+    /// End-of-game house rules (forbidden finishing cards, special losses)
+    #[serde(default)]
+    pub end_rules: Vec<EndRule>,
+    /// Direction in which reflected attacks are passed on
+    #[serde(default)]
+    pub reflect_direction: ReflectDirection,
+    /// Maximum number of reflects (including trump reflects) per trick (None: unlimited)
+    #[serde(default)]
+    pub max_reflects_per_trick: Option<u32>,
+    /// If true, an attack may not be reflected back to the player who opened the trick
+    #[serde(default)]
+    pub no_reflect_to_original_attacker: bool,
+    /// Whether attackers add cards in turn or concurrently
+    #[serde(default)]
+    pub turn_model: TurnModel,
+}
+
+fn default_hand_size() -> usize { 6 }
+
+fn default_decks() -> usize { 1 }
+
+impl GameConfig {
+    /// The deck used by this config: `custom_deck`, or else the standard deck of `deck_size` cards.
+    pub fn deck_spec(&self) -> Result<DeckSpec, EngineError> {
+        match &self.custom_deck {
+            Some(spec) => Ok(spec.clone()),
+            None => DeckSpec::standard(self.deck_size),
+        }
+    }
+
+    /// All cards in play: `decks` copies of the deck (private).
+    pub fn deck(&self) -> Result<Vec<Card>, EngineError> {
+        let deck = self.deck_spec()?.cards();
+        let mut out = Vec::with_capacity(deck.len() * self.decks);
+        for _ in 0..self.decks {
+            out.extend_from_slice(&deck);
+        }
+        Ok(out)
+    }
+
+    /// Total number of cards in play.
+    pub fn total_cards(&self) -> Result<usize, EngineError> {
+        Ok(self.deck_spec()?.len() * self.decks)
+    }
+
+    /// Check that a game can be dealt with this config.
+    pub fn validate(&self) -> Result<(), EngineError> {
+        self.deck_spec()?.validate()?;
+        if self.decks == 0 {
+            return Err(EngineError::setup(SetupError::NoDecks));
+        }
+        if self.num_players < 2 {
+            return Err(EngineError::setup(SetupError::TooFewPlayers));
+        }
+        if self.num_players > u8::MAX as usize + 1 {
+            return Err(EngineError::setup(SetupError::TooManyPlayers { num_players: self.num_players }));
+        }
+        let total = self.total_cards()?;
+        if self.hand_size == 0 || self.num_players * self.hand_size > total {
+            return Err(EngineError::setup(SetupError::CannotDeal {
+                hand_size: self.hand_size,
+                num_players: self.num_players,
+                cards: total,
+            }));
+        }
+        if self.max_piles == Some(0) || self.first_trick_limit == Some(0) {
+            return Err(EngineError::setup(SetupError::NoPilesAllowed));
+        }
+        if !self.teams.is_empty() && self.teams.len() != self.num_players {
+            return Err(EngineError::setup(SetupError::TeamSeats { seats: self.teams.len(), num_players: self.num_players }));
+        }
+        Ok(())
+    }
 }
 
 impl Default for GameConfig {
     fn default() -> Self {
         Self {
             deck_size: 36,
+            custom_deck: None,
+            joker_matching: JokerMatching::Jokers,
+            decks: default_decks(),
             num_players: 2,
             trump_reflecting: false,
             reflecting: false,
+            hand_size: default_hand_size(),
+            max_piles: None,
+            first_trick_limit: None,
+            throw_in: ThrowIn::Everyone,
+            multi_card_attacks: false,
+            trump_exchange: false,
+            teams: vec![],
+            end_rules: vec![],
+            reflect_direction: ReflectDirection::Clockwise,
+            max_reflects_per_trick: None,
+            no_reflect_to_original_attacker: false,
+            turn_model: TurnModel::Sequential,
         }
     }
 }
 
-#[derive(Debug, thiserror::Error)]
+/// Why an action or a game setup was rejected.
+/// Serialized with a stable snake_case `code` next to the variant's fields.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
+#[serde(tag = "code", rename_all = "snake_case")]
 pub enum EngineError {
-    #[error("illegal move: {0}")]
-    IllegalMove(String),
+    /// The action does not exist in the current phase.
+    #[error("{action} is not allowed in the {phase:?} phase")]
+    WrongPhase { phase: Phase, action: ActionKind },
+    /// The action was submitted by someone who may not act now.
+    #[error("it is not {player}'s turn ({to_move} to move)")]
+    NotYourTurn { player: PlayerId, to_move: PlayerId },
+    #[error("{card} is not in {player}'s hand")]
+    CardNotInHand { player: PlayerId, card: Card },
+    #[error("{card} does not beat {attack} on pile {pile}")]
+    DoesNotBeat { pile: usize, card: Card, attack: Card },
+    /// A card was added to a trick without its rank being on the table.
+    #[error("the rank of {card} is not on the table")]
+    RankNotOnTable { card: Card },
+    /// The defender cannot be given more undefended piles than cards in hand.
+    #[error("{defender} cannot take on more piles")]
+    DefenderAtCapacity { defender: PlayerId },
+    /// The trick already holds the maximum number of piles allowed by the config.
+    #[error("the trick is limited to {limit} pile(s)")]
+    PileLimit { limit: usize },
+    /// The pile does not exist or is already defended.
+    #[error("pile {pile} is not open")]
+    PileNotOpen { pile: usize },
+    #[error("cannot stop attacking while piles are undefended")]
+    PilesUndefended,
+    #[error("cannot reflect: {reason}")]
+    ReflectNotAllowed { reason: ReflectError },
+    #[error("invalid multi-card play: {reason}")]
+    InvalidMultiCard { reason: MultiCardError },
+    #[error("cannot exchange the trump card")]
+    TrumpExchangeNotAllowed,
+    /// An end-of-game rule forbids going out with this card.
+    #[error("cannot go out with {card}")]
+    ForbiddenFinish { card: Card },
+    #[error("seat P{seat} does not exist with {num_players} players")]
+    InvalidSeat { seat: usize, num_players: usize },
+    #[error("invalid card notation: {notation:?}")]
+    InvalidNotation { notation: String },
+    /// The config or the manual start position cannot be played.
+    #[error("invalid setup: {reason}")]
+    InvalidSetup { reason: SetupError },
+    /// A manual correction would leave the game in an impossible state.
+    #[error("inconsistent game state: {reason}")]
+    InvalidState { reason: StateError },
+    #[error("the game is not finished")]
+    GameNotFinished,
+    #[error("the match is already over")]
+    MatchOver,
+}
+
+impl EngineError {
+    fn setup(reason: SetupError) -> Self {
+        EngineError::InvalidSetup { reason }
+    }
+
+    fn inconsistent(reason: StateError) -> Self {
+        EngineError::InvalidState { reason }
+    }
+}
+
+/// Why a reflect (or trump reflect) was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
+#[serde(rename_all = "snake_case")]
+pub enum ReflectError {
+    #[error("reflecting is not enabled")]
+    Disabled,
+    #[error("trump reflecting is not enabled")]
+    TrumpDisabled,
+    #[error("a pile has already been defended")]
+    AlreadyDefended,
+    #[error("there is no open pile")]
+    NoOpenPile,
+    #[error("the card must have the rank of the attack")]
+    RankMismatch,
+    #[error("a trump reflect requires a trump")]
+    NotTrump,
+    #[error("this trump has already been shown in this trick")]
+    TrumpAlreadyShown,
+    #[error("the reflect limit for this trick is reached")]
+    LimitReached,
+    #[error("the target is not a neighbouring opponent in the allowed direction")]
+    DirectionNotAllowed,
+    #[error("the attack may not go back to the original attacker")]
+    OriginalAttacker,
+    #[error("there is no other active player")]
+    NoTarget,
+    #[error("the new defender does not have enough cards")]
+    TargetCannotDefend,
+}
+
+/// Why a multi-card play (`AttackMany`, `ThrowMany`) was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
+#[serde(rename_all = "snake_case")]
+pub enum MultiCardError {
+    #[error("multi-card attacks are not enabled")]
+    Disabled,
+    #[error("no cards given")]
+    NoCards,
+    #[error("a card is given more often than the decks hold it")]
+    TooManyCopies,
+    #[error("cards must have the same rank")]
+    RankMismatch,
+}
+
+/// Why a config or a manual start position was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SetupError {
+    #[error("unsupported deck size {size} (expected 32, 36, 40, 44, 48 or 52)")]
+    UnsupportedDeckSize { size: usize },
+    #[error("a deck needs at least one rank")]
+    NoRanks,
+    #[error("deck ranks must not repeat")]
+    RepeatedRank,
+    #[error("jokers are added with `jokers`, not as a rank")]
+    JokerRank,
+    #[error("at least one deck is required")]
+    NoDecks,
+    #[error("at least two players are required")]
+    TooFewPlayers,
+    #[error("too many players: {num_players}")]
+    TooManyPlayers { num_players: usize },
+    #[error("cannot deal {hand_size} cards to {num_players} players from {cards} cards")]
+    CannotDeal { hand_size: usize, num_players: usize, cards: usize },
+    #[error("pile limits must allow at least one pile")]
+    NoPilesAllowed,
+    #[error("team assignment has {seats} seats but there are {num_players} players")]
+    TeamSeats { seats: usize, num_players: usize },
+    #[error("manual start requires cards left in the stock")]
+    EmptyStock,
+    #[error("{card} is not part of the deck")]
+    CardNotInDeck { card: Card },
+    #[error("manual start requires exactly {expected} cards in your hand")]
+    WrongHandSize { expected: usize },
+    #[error("the face-up trump card must be a suited card")]
+    TrumpCardNotSuited,
+    #[error("your hand size is given by your cards")]
+    OwnHandSizeGiven,
+    #[error("hand size of {player} given twice")]
+    HandSizeGivenTwice { player: PlayerId },
+    #[error("missing hand size of {player}")]
+    MissingHandSize { player: PlayerId },
+    #[error("a card is entered more often than the deck holds it")]
+    TooManyCopies,
+    #[error("{entered} cards entered, but the deck only has {cards}")]
+    TooManyCards { entered: usize, cards: usize },
+    #[error("the attacker cannot defend against themselves")]
+    AttackerDefends,
+    #[error("{player} cannot have gone out")]
+    CannotHaveGoneOut { player: PlayerId },
+    #[error("the defender can only take cards on the table")]
+    NothingToTake,
+    #[error("the attacker and defender must still be in the game")]
+    PlayerOut,
+    #[error("the defender has fewer cards than open piles")]
+    DefenderShortOfCards,
+    #[error("{card} does not beat {attack} on pile {pile}")]
+    DefenseDoesNotBeat { pile: usize, card: Card, attack: Card },
+}
+
+/// Why a game state (e.g. after a manual correction) is impossible.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StateError {
+    #[error("the card must be known")]
+    UnknownCard,
+    #[error("{card} has not been played")]
+    NotPlayed { card: Card },
+    #[error("not enough cards in the stock")]
+    StockExhausted,
+    #[error("{player} has no unknown card to put back")]
+    NoUnknownCard { player: PlayerId },
+    #[error("the face-up trump card is not known")]
+    TrumpCardUnknown,
+    #[error("{card} is still at the bottom of the stock")]
+    TrumpCardInStock { card: Card },
+    #[error("{card} is not in {zone:?}")]
+    NotInZone { card: Card, zone: Zone },
+    #[error("every {card} is already located elsewhere")]
+    AlreadyLocated { card: Card },
+    #[error("{cards} cards in play, but the deck has {deck}")]
+    WrongCardCount { cards: usize, deck: usize },
+    #[error("{card} is not part of the deck")]
+    CardNotInDeck { card: Card },
+    #[error("a card is in more places than the deck holds it")]
+    TooManyCopies,
+    #[error("cards on the table must be known")]
+    UnknownTableCard,
+    #[error("the bottom card of the stock must be the face-up trump card")]
+    BottomCardNotTrump,
+    #[error("piles are undefended while attacking")]
+    UndefendedWhileAttacking,
+    #[error("there is no open pile to defend")]
+    NoOpenPile,
+    #[error("there are no cards on the table to take")]
+    NothingToTake,
+    #[error("the attacker cannot defend against themselves")]
+    AttackerDefends,
+    #[error("the defender has fewer cards than open piles")]
+    DefenderShortOfCards,
+    #[error("{hands} hands for {num_players} players")]
+    WrongHandCount { hands: usize, num_players: usize },
+    #[error("{card} does not beat {attack} on pile {pile}")]
+    DefenseDoesNotBeat { pile: usize, card: Card, attack: Card },
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum Action {
     /// Attacker plays an attacking card.
     Attack { card: Card },
     /// Attacker ends attack (like `passattack` in github-durak).
     PassAttack,
+    /// Attacker plays several cards at once (requires `config.multi_card_attacks`).
+    /// On an empty table all cards must share a rank, otherwise each rank must be on the table.
+    AttackMany { cards: Vec<Card> },
 
     /// Defender defends a pile.
     Defend { pile_index: usize, card: Card },
@@ -230,14 +910,93 @@ pub enum Action {
 
     /// Attacker throws an extra card after a take, or None to finish throwing.
     Throw { card: Option<Card> },
+    /// Attacker throws several extra cards at once after a take (requires `config.multi_card_attacks`).
+    ThrowMany { cards: Vec<Card> },
 
     /// Defender reflects with a card of the same rank (requires `config.reflecting`).
-    /// The defender plays the card and the attack is redirected to the next player.
-    Reflect { card: Card },
+    /// The defender plays the card and the attack is redirected to the next player
+    /// (or to `to`, which is set when `config.reflect_direction` lets the defender choose).
+    Reflect {
+        card: Card,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        to: Option<PlayerId>,
+    },
 
     /// Defender shows a trump card of the same rank without playing it (requires `config.trump_reflecting`).
     /// The card stays in hand, but the attack is redirected. Can only be used once per trump per turn.
-    ReflectTrump { card: Card },
+    ReflectTrump {
+        card: Card,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        to: Option<PlayerId>,
+    },
+
+    /// Player to move swaps the lowest trump in the deck for the face-up trump at the bottom of the
+    /// stock while other cards are left above it (requires `config.trump_exchange`). Both cards
+    /// become public; the turn does not change.
+    ExchangeTrump,
+}
+
+impl Action {
+    /// The kind of this action, without its cards.
+    pub fn kind(&self) -> ActionKind {
+        match self {
+            Action::Attack { .. } => ActionKind::Attack,
+            Action::PassAttack => ActionKind::PassAttack,
+            Action::AttackMany { .. } => ActionKind::AttackMany,
+            Action::Defend { .. } => ActionKind::Defend,
+            Action::Take => ActionKind::Take,
+            Action::Throw { .. } => ActionKind::Throw,
+            Action::ThrowMany { .. } => ActionKind::ThrowMany,
+            Action::Reflect { .. } => ActionKind::Reflect,
+            Action::ReflectTrump { .. } => ActionKind::ReflectTrump,
+            Action::ExchangeTrump => ActionKind::ExchangeTrump,
+        }
+    }
+
+    /// The serialized `type` of this action (e.g. `"attack"`).
+    pub fn name(&self) -> &'static str {
+        self.kind().name()
+    }
+}
+
+/// The kind of an `Action` (its serialized `type`), e.g. to report which action was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
+#[serde(rename_all = "snake_case")]
+pub enum ActionKind {
+    Attack,
+    PassAttack,
+    AttackMany,
+    Defend,
+    Take,
+    Throw,
+    ThrowMany,
+    Reflect,
+    ReflectTrump,
+    ExchangeTrump,
+}
+
+impl ActionKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ActionKind::Attack => "attack",
+            ActionKind::PassAttack => "pass_attack",
+            ActionKind::AttackMany => "attack_many",
+            ActionKind::Defend => "defend",
+            ActionKind::Take => "take",
+            ActionKind::Throw => "throw",
+            ActionKind::ThrowMany => "throw_many",
+            ActionKind::Reflect => "reflect",
+            ActionKind::ReflectTrump => "reflect_trump",
+            ActionKind::ExchangeTrump => "exchange_trump",
+        }
+    }
+}
+
+impl std::fmt::Display for ActionKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
 }
 
 /// Game state with full card tracking.
@@ -249,10 +1008,15 @@ pub enum Action {
 /// Call `determinize(perspective, rng)` before MCTS to create a state where
 /// all cards are known from the perspective player's viewpoint.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
 pub struct GameState {
     /// Trump suit.
     pub trump: Suit,
 
+    /// The face-up card at the bottom of the stock, also after it has been drawn.
+    #[serde(default)]
+    pub trump_card: Option<Card>,
+
     /// Primary attacker (first in seat order among attackers).
     pub attacker: PlayerId,
     pub defender: PlayerId,
@@ -290,22 +1054,60 @@ pub struct GameState {
     #[serde(default)]
     pub reflected_trumps: Vec<Card>,
 
+    /// Main attacker who opened the current trick (before any reflects).
+    #[serde(default)]
+    pub original_attacker: PlayerId,
+
+    /// Number of reflects (including trump reflects) in the current trick.
+    #[serde(default)]
+    pub reflects: u32,
+
+    /// Number of tricks finished so far (0 during the first trick).
+    #[serde(default)]
+    pub tricks_played: u32,
+
+    /// Players who have gone out, in finishing order.
+    /// Players who emptied their hands during the same trick share a group (and a placement).
+    #[serde(default)]
+    pub finished: Vec<Vec<PlayerId>>,
+
+    /// Attackers who voted to stop adding cards to the current trick (`TurnModel::Simultaneous`).
+    #[serde(default)]
+    pub done_attackers: Vec<PlayerId>,
+
+    /// Proven voids (cards players are known not to hold), used for deduction.
+    #[serde(default)]
+    pub voids: Vec<Void>,
+
     /// Game configuration.
     pub config: GameConfig,
 }
 
 impl GameState {
     /// Build the list of attackers for a new trick.
-    /// Attackers are all active players excluding the defender.
+    /// Attackers are the active players excluding the defender who may throw in under `config.throw_in`.
     /// The primary attacker (main_attacker) should be first in the list.
     fn build_attackers(&self, main_attacker: PlayerId) -> Vec<PlayerId> {
         let num_players = self.num_players();
         let mut attackers = Vec::with_capacity(num_players - 1);
 
+        // If the main attacker is out (e.g. reflected with their last card), the player before the defender leads
+        let lead = if self.is_player_active(main_attacker) {
+            main_attacker
+        } else {
+            self.prev_active_player(self.defender)
+        };
+        let neighbours = [self.prev_active_player(self.defender), self.next_active_player(self.defender)];
+
         // Start from main_attacker and go around, adding players who are still in the game
         let mut pid = main_attacker;
         for _ in 0..num_players {
-            if pid != self.defender && self.is_player_active(pid) {
+            let may_throw = match self.config.throw_in {
+                ThrowIn::Everyone => true,
+                ThrowIn::Neighbours => pid == lead || neighbours.contains(&pid),
+                ThrowIn::MainAttacker => pid == lead,
+            };
+            if pid != self.defender && self.is_player_active(pid) && may_throw && !self.are_teammates(pid, self.defender) {
                 attackers.push(pid);
             }
             pid = pid.next(num_players);
@@ -335,6 +1137,31 @@ impl GameState {
         }
     }
 
+    /// Find the next active player after the given player who is not on their team.
+    fn next_opponent(&self, from: PlayerId) -> PlayerId {
+        let mut pid = self.next_active_player(from);
+        for _ in 1..self.num_players() {
+            if !self.are_teammates(from, pid) {
+                return pid;
+            }
+            pid = self.next_active_player(pid);
+        }
+        from // No active opponent found, return original
+    }
+
+    /// Find the closest active player before the given player (counter-clockwise).
+    fn prev_active_player(&self, from: PlayerId) -> PlayerId {
+        let num_players = self.num_players();
+        let mut pid = from;
+        for _ in 1..num_players {
+            pid = pid.prev(num_players);
+            if self.is_player_active(pid) {
+                return pid;
+            }
+        }
+        from // No active player found, return original
+    }
+
     /// Start a new trick with the given main attacker.
     fn new_trick(&mut self, main_attacker: PlayerId) {
         // If main_attacker is out of the game, find next active player
@@ -344,8 +1171,8 @@ impl GameState {
             self.next_active_player(main_attacker)
         };
 
-        // Find the defender (next active player after main_attacker)
-        let defender = self.next_active_player(main_attacker);
+        // Find the defender (next active player after main_attacker, skipping teammates)
+        let defender = self.next_opponent(main_attacker);
 
         self.attacker = main_attacker;
         self.defender = defender;
@@ -355,45 +1182,148 @@ impl GameState {
         self.throw_start_idx = 0;
         self.phase = Phase::Attacking;
         self.reflected_trumps.clear();
+        self.original_attacker = main_attacker;
+        self.reflects = 0;
+        self.done_attackers.clear();
         self.table.clear();
+        self.tricks_played += 1;
+    }
+
+    fn is_simultaneous(&self) -> bool {
+        self.config.turn_model == TurnModel::Simultaneous
+    }
+
+    /// The attacker whose turn it is to add cards (or pass).
+    fn current_attacker(&self) -> PlayerId {
+        if self.attackers.is_empty() {
+            self.attacker
+        } else {
+            self.attackers[self.current_attacker_idx % self.attackers.len()]
+        }
+    }
+
+    /// Record that `pid` is done adding cards to this trick (`TurnModel::Simultaneous`) and move the
+    /// turn on to the next attacker who is not done. Attackers who went out have nothing to add and
+    /// are not waited on. Returns true once every attacker is done.
+    fn vote_done(&mut self, pid: PlayerId) -> bool {
+        if !self.done_attackers.contains(&pid) {
+            self.done_attackers.push(pid);
+        }
+        let n = self.attackers.len();
+        for i in 0..n {
+            let idx = (self.current_attacker_idx + i) % n;
+            let p = self.attackers[idx];
+            if !self.done_attackers.contains(&p) && self.hand_size(p) > 0 {
+                self.current_attacker_idx = idx;
+                return false;
+            }
+        }
+        true
+    }
+
+    /// End the trick after a successful defense: the table is discarded.
+    fn finish_defended_trick(&mut self) {
+        // Discard the table (cards stay public in discard)
+        for p in self.table.drain(..) {
+            self.discard.push(p.attack);
+            if let Some(d) = p.defense {
+                self.discard.push(d);
+            }
+        }
+
+        // Refill hands in draw order (attackers first, then defender)
+        self.refill_hands();
+
+        // Start new trick with defender as main attacker
+        self.new_trick(self.defender);
+    }
+
+    /// End the trick with the defender taking every card on the table.
+    fn finish_taken_trick(&mut self) {
+        let taken: Vec<Card> = self.table.drain(..)
+            .flat_map(|p| {
+                let mut cards = vec![p.attack];
+                if let Some(d) = p.defense {
+                    cards.push(d);
+                }
+                cards
+            })
+            .collect();
+
+        // Cards taken are public (they were on the table)
+        self.hands[self.defender.index()].extend(taken);
+
+        // Refill hands in draw order (attackers first, then defender)
+        self.refill_hands();
+
+        // Start new trick - main attacker is player after the defender
+        let num_players = self.num_players();
+        let new_main_attacker = self.defender.next(num_players);
+        self.new_trick(new_main_attacker);
     }
 
     /// Create a new computer game with shuffled deck.
     /// All cards are known to the system but private to their holders until played.
-    pub fn new_computer_game(seed: u64, config: GameConfig) -> Self {
+    /// The player with the lowest trump attacks first.
+    pub fn new_computer_game(seed: u64, config: GameConfig) -> Result<Self, EngineError> {
+        Self::deal(seed, config, None)
+    }
+
+    /// Create a new computer game where `attacker` attacks first
+    /// (instead of the lowest trump rule, e.g. for follow-up games in a `Match`).
+    pub fn new_computer_game_with_attacker(
+        seed: u64,
+        config: GameConfig,
+        attacker: PlayerId,
+    ) -> Result<Self, EngineError> {
+        attacker.validate(config.num_players)?;
+        Self::deal(seed, config, Some(attacker))
+    }
+
+    fn deal(seed: u64, config: GameConfig, first_attacker: Option<PlayerId>) -> Result<Self, EngineError> {
+        config.validate()?;
         let mut rng = StdRng::seed_from_u64(seed);
 
         // Create a deck of private cards and shuffle it
-        let mut deck = full_deck(config.deck_size);
+        let mut deck = config.deck()?;
         for i in (1..deck.len()).rev() {
             let j = rng.gen_range(0..=i);
             deck.swap(i, j);
         }
 
-        // Trump card is at the bottom (last card), public
+        // Trump card is at the bottom (last card), public; a joker can't set the trump suit
+        if let Some(i) = deck.iter().position(|c| !c.is_joker()) {
+            deck.swap(0, i);
+        }
         deck[0] = deck[0].as_public();
         let trump = deck[0].suit();
 
-        // Deal 6 cards to each player (private)
+        // Deal hand_size cards to each player (private)
         // Track who has the lowest trump to determine starting player
         let mut hands: Vec<Vec<Card>> = Vec::with_capacity(config.num_players);
         let mut lowest_trump: Option<(usize, Rank)> = None; // (player_index, rank)
+        let mut voids = Vec::new();
 
         for player_idx in 0..config.num_players {
-            let mut hand: Vec<Card> = deck.drain(deck.len() - 6..).collect();
+            let mut hand: Vec<Card> = deck.drain(deck.len() - config.hand_size..).collect();
 
             // Find lowest trump and make it public (announced at game start)
-            if let Some(lowest_trump_idx) = hand.iter()
+            let lowest_trump_idx = hand.iter()
                 .enumerate()
-                .filter(|(_, c)| c.suit() == trump)
+                .filter(|(_, c)| c.is_trump(trump))
                 .min_by_key(|(_, c)| c.rank() as u8)
-                .map(|(i, _)| i)
-            {
+                .map(|(i, _)| i);
+            if first_attacker.is_none() {
+                // The announcement proves there is no lower trump (or no trump at all) in the hand
+                let below = lowest_trump_idx.map(|i| hand[i].rank());
+                voids.push(Void { player: PlayerId::from_index(player_idx), suit: trump, below });
+            }
+            if first_attacker.is_none() && let Some(lowest_trump_idx) = lowest_trump_idx {
                 let rank = hand[lowest_trump_idx].rank();
                 hand[lowest_trump_idx] = hand[lowest_trump_idx].as_public();
 
                 // Track global lowest trump
-                if lowest_trump.map_or(true, |(_, r)| rank < r) {
+                if lowest_trump.is_none_or(|(_, r)| rank < r) {
                     lowest_trump = Some((player_idx, rank));
                 }
             }
@@ -405,25 +1335,16 @@ impl GameState {
         let stock: Vec<Card> = deck;
 
         // Player with lowest trump starts, default to P0 if no one has trumps
-        let attacker = PlayerId::from_index(lowest_trump.map_or(0, |(idx, _)| idx));
-        let defender = attacker.next(config.num_players);
-
-        // Build attackers list (all players except defender)
-        let mut attackers = Vec::with_capacity(config.num_players - 1);
-        let mut pid = attacker;
-        for _ in 0..config.num_players {
-            if pid != defender {
-                attackers.push(pid);
-            }
-            pid = pid.next(config.num_players);
-        }
+        let attacker = first_attacker
+            .unwrap_or_else(|| PlayerId::from_index(lowest_trump.map_or(0, |(idx, _)| idx)));
 
-        Self {
+        let mut state = Self {
             trump,
+            trump_card: stock.first().copied(),
             attacker,
-            defender,
+            defender: attacker,
             phase: Phase::Attacking,
-            attackers,
+            attackers: vec![],
             current_attacker_idx: 0,
             last_played_attacker: attacker,
             throw_start_idx: 0,
@@ -432,62 +1353,98 @@ impl GameState {
             table: vec![],
             discard: vec![],
             reflected_trumps: vec![],
+            original_attacker: attacker,
+            reflects: 0,
+            tricks_played: 0,
+            finished: vec![],
+            done_attackers: vec![],
+            voids,
             config,
-        }
+        };
+        state.defender = state.next_opponent(attacker);
+        state.attackers = state.build_attackers(attacker);
+        Ok(state)
     }
 
-    /// Create a manual game where only your hand is known.
-    /// Your cards are private, opponent cards are unknown.
+    /// Create a manual game where only your hand is known, with you at `seat`.
+    /// Your cards are private, opponent cards are unknown apart from the trumps they declared
+    /// (players missing from `opponent_trumps` declared nothing).
     pub fn new_manual_game(
         trump_card: Card,
         player_hand: Vec<Card>,
+        seat: u8,
         starting_player: u8,
-        opponent_lowest_trumps: Vec<(PlayerId, Option<Rank>)>,
+        opponent_trumps: Vec<(PlayerId, TrumpDeclaration)>,
         config: GameConfig,
     ) -> Result<Self, EngineError> {
-        if player_hand.len() != 6 {
-            return Err(EngineError::IllegalMove(
-                "manual start requires exactly 6 cards in your hand".into(),
-            ));
+        config.validate()?;
+        // The face-up trump card must be left in the stock
+        let total = config.total_cards()?;
+        if config.num_players * config.hand_size == total {
+            return Err(EngineError::setup(SetupError::EmptyStock));
+        }
+        let spec = config.deck_spec()?;
+        if let Some(card) = player_hand.iter().chain([&trump_card]).find(|c| !spec.contains(c)) {
+            return Err(EngineError::setup(SetupError::CardNotInDeck { card: *card }));
+        }
+        if player_hand.len() != config.hand_size {
+            return Err(EngineError::setup(SetupError::WrongHandSize { expected: config.hand_size }));
+        }
+        let me = PlayerId::new(seat as usize, config.num_players)?;
+        let attacker = PlayerId::new(starting_player as usize, config.num_players)?;
+        for (pid, _) in &opponent_trumps {
+            pid.validate(config.num_players)?;
         }
 
+        if trump_card.is_unknown() || trump_card.is_joker() {
+            return Err(EngineError::setup(SetupError::TrumpCardNotSuited));
+        }
         let trump = trump_card.suit();
 
-        // Build P0's hand (private, except lowest trump which is public)
-        let mut p0_hand: Vec<Card> = player_hand.iter()
+        // Build your hand (private, except lowest trump which is public)
+        let mut my_hand: Vec<Card> = player_hand.iter()
             .map(|c| c.as_private())
             .collect();
 
         // Make lowest trump public
-        if let Some(lowest_trump_idx) = p0_hand.iter()
+        if let Some(lowest_trump_idx) = my_hand.iter()
             .enumerate()
-            .filter(|(_, c)| c.suit() == trump)
+            .filter(|(_, c)| c.is_trump(trump))
             .min_by_key(|(_, c)| c.rank() as u8)
             .map(|(i, _)| i)
         {
-            p0_hand[lowest_trump_idx] = p0_hand[lowest_trump_idx].as_public();
+            my_hand[lowest_trump_idx] = my_hand[lowest_trump_idx].as_public();
         }
 
         let mut hands: Vec<Vec<Card>> = Vec::with_capacity(config.num_players);
-        hands.push(p0_hand);
+        let mut voids = Vec::new();
 
         // Opponents: declared lowest trump is public, rest are unknown
-        for i in 1..config.num_players {
+        for i in 0..config.num_players {
             let pid = PlayerId::from_index(i);
-            let lowest_trump_rank = opponent_lowest_trumps
+            if pid == me {
+                hands.push(std::mem::take(&mut my_hand));
+                continue;
+            }
+            let declaration = opponent_trumps
                 .iter()
                 .find(|(p, _)| *p == pid)
-                .and_then(|(_, rank_opt)| *rank_opt);
+                .map_or(TrumpDeclaration::Undeclared, |(_, d)| *d);
 
-            let mut hand = Vec::with_capacity(6);
+            let mut hand = Vec::with_capacity(config.hand_size);
 
-            // Add the declared lowest trump as public if specified
-            if let Some(rank) = lowest_trump_rank {
-                hand.push(Card::public(trump, rank));
+            // The declared lowest trump is public, and there is no lower trump in that hand
+            match declaration {
+                TrumpDeclaration::Lowest { rank } => {
+                    hand.push(Card::public(trump, rank));
+                    voids.push(Void { player: pid, suit: trump, below: Some(rank) });
+                }
+                TrumpDeclaration::NoTrump => voids.push(Void { player: pid, suit: trump, below: None }),
+                TrumpDeclaration::Undeclared => {}
             }
 
             // Fill rest with unknown cards
-            while hand.len() < 6 {
+            while hand.len() < config.hand_size {
                 hand.push(Card::Unknown);
             }
 
@@ -495,32 +1452,20 @@ impl GameState {
         }
 
         // Stock: trump card at bottom (public), rest unknown
-        let stock_size = config.deck_size - config.num_players * 6;
+        let stock_size = total - config.num_players * config.hand_size;
         let mut stock = Vec::with_capacity(stock_size);
         stock.push(trump_card.as_public());
         for _ in 1..stock_size {
             stock.push(Card::Unknown);
         }
 
-        let attacker = PlayerId::from_index(starting_player as usize);
-        let defender = attacker.next(config.num_players);
-
-        // Build attackers list (all players except defender)
-        let mut attackers = Vec::with_capacity(config.num_players - 1);
-        let mut pid = attacker;
-        for _ in 0..config.num_players {
-            if pid != defender {
-                attackers.push(pid);
-            }
-            pid = pid.next(config.num_players);
-        }
-
-        Ok(Self {
+        let mut state = Self {
             trump,
+            trump_card: Some(trump_card.as_public()),
             attacker,
-            defender,
+            defender: attacker,
             phase: Phase::Attacking,
-            attackers,
+            attackers: vec![],
             current_attacker_idx: 0,
             last_played_attacker: attacker,
             throw_start_idx: 0,
@@ -529,63 +1474,179 @@ impl GameState {
             table: vec![],
             discard: vec![],
             reflected_trumps: vec![],
+            original_attacker: attacker,
+            reflects: 0,
+            tricks_played: 0,
+            finished: vec![],
+            done_attackers: vec![],
+            voids,
             config,
-        })
+        };
+        state.defender = state.next_opponent(attacker);
+        state.attackers = state.build_attackers(attacker);
+        Ok(state)
     }
 
     pub fn hand_size(&self, pid: PlayerId) -> usize {
-        self.hands[pid as usize].len()
+        self.hands[pid.index()].len()
     }
 
     pub fn num_players(&self) -> usize {
         self.hands.len()
     }
 
-    /// Returns the durak (loser) if game is terminal.
-    pub fn durak(&self) -> Option<PlayerId> {
+    /// Team of a player, or None when not playing in teams.
+    pub fn team_of(&self, pid: PlayerId) -> Option<u8> {
+        self.config.teams.get(pid.index()).copied()
+    }
+
+    /// Check if two different players are on the same team.
+    fn are_teammates(&self, a: PlayerId, b: PlayerId) -> bool {
+        a != b && self.team_of(a).is_some() && self.team_of(a) == self.team_of(b)
+    }
+
+    /// Players still holding cards.
+    fn players_with_cards(&self) -> Vec<PlayerId> {
+        (0..self.num_players())
+            .map(PlayerId::from_index)
+            .filter(|&pid| self.hand_size(pid) > 0)
+            .collect()
+    }
+
+    /// Returns the final outcome with placements if the game is terminal.
+    pub fn outcome(&self) -> Option<Outcome> {
         if !self.is_terminal() {
             return None;
         }
 
-        let mut durak = None;
-        for i in 0..self.num_players() {
-            let pid = PlayerId::from_index(i);
-            if self.hand_size(pid) > 0 {
-                if durak.is_some() {
-                    return None; // Multiple players with cards = tie
+        let mut placements = self.finished.clone();
+
+        // States saved before finishing order was tracked may miss some exits
+        let unrecorded: Vec<PlayerId> = (0..self.num_players())
+            .map(PlayerId::from_index)
+            .filter(|&pid| self.hand_size(pid) == 0 && !placements.iter().flatten().any(|&p| p == pid))
+            .collect();
+        if !unrecorded.is_empty() {
+            placements.push(unrecorded);
+        }
+
+        let holders = self.players_with_cards();
+        let specials = self.special_outcomes(&holders);
+        match holders.as_slice() {
+            [] => Some(Outcome::Draw { placements }),
+            [durak] => Some(Outcome::Durak { durak: *durak, placements, specials }),
+            // Opponents left who may not go out (see `nobody_can_go_out`)
+            [first, rest @ ..] if rest.iter().any(|&p| !self.are_teammates(*first, p)) => {
+                Some(Outcome::Draw { placements })
+            }
+            [first, ..] => Some(Outcome::TeamDurak {
+                team: self.team_of(*first).unwrap_or_default(),
+                players: holders.clone(),
+                placements,
+                specials,
+            }),
+        }
+    }
+
+    /// Special results for the players left holding cards, per `config.end_rules`.
+    fn special_outcomes(&self, losers: &[PlayerId]) -> Vec<SpecialOutcome> {
+        let mut specials = Vec::new();
+        for rule in &self.config.end_rules {
+            let EndRule::Epaulettes { rank } = rule else {
+                continue;
+            };
+            for &player in losers {
+                let count = self.hands[player.index()]
+                    .iter()
+                    .filter(|c| !c.is_unknown() && c.rank() == *rank)
+                    .count();
+                if count >= 2 {
+                    specials.push(SpecialOutcome::Epaulettes { player, rank: *rank });
                 }
-                durak = Some(pid);
             }
         }
-        durak
+        specials
+    }
+
+    /// The card forbidden by `config.end_rules` if an action would make `pid` go out with it.
+    fn forbidden_finish(&self, pid: PlayerId, action: &Action) -> Option<Card> {
+        if self.config.end_rules.is_empty() || !self.stock.is_empty() {
+            return None;
+        }
+        let cards = match action {
+            Action::Attack { card } | Action::Defend { card, .. } | Action::Reflect { card, .. } => vec![*card],
+            Action::Throw { card: Some(card) } => vec![*card],
+            Action::AttackMany { cards } | Action::ThrowMany { cards } => cards.clone(),
+            _ => return None,
+        };
+        if self.hand_size(pid) != cards.len() {
+            return None;
+        }
+        cards.into_iter().find(|c| self.config.end_rules.iter().any(|r| r.forbids_finish(c, self.trump)))
+    }
+
+    /// Returns the durak (loser) if game is terminal.
+    /// Returns None for a draw, or when several teammates are left holding cards (see `losing_team`).
+    pub fn durak(&self) -> Option<PlayerId> {
+        if !self.is_terminal() {
+            return None;
+        }
+
+        match self.players_with_cards().as_slice() {
+            [durak] => Some(*durak),
+            _ => None, // No or multiple players with cards = tie (or a team loss)
+        }
+    }
+
+    /// Returns the team of the durak if the game is terminal and played in teams.
+    pub fn losing_team(&self) -> Option<u8> {
+        let outcome = self.outcome()?;
+        outcome.losers().first().and_then(|&pid| self.team_of(pid))
     }
 
     pub fn is_terminal(&self) -> bool {
         if !self.stock.is_empty() {
             return false;
         }
-        let players_with_cards = (0..self.num_players())
-            .filter(|&i| self.hand_size(PlayerId::from_index(i)) > 0)
-            .count();
-        players_with_cards <= 1
+        let holders = self.players_with_cards();
+        if holders.len() == 1
+            && self.phase == Phase::Defending
+            && self.hand_size(self.defender) == self.undefended_pile_count()
+        {
+            // The defender can still go out together with the last attacker (a draw)
+            return false;
+        }
+        if holders.len() > 1 && holders.iter().all(|&pid| pid == holders[0] || self.are_teammates(pid, holders[0])) {
+            // Only teammates are left, and they never attack each other
+            return true;
+        }
+        if holders.len() > 1 && self.table.is_empty() && self.nobody_can_go_out(&holders) {
+            return true;
+        }
+        holders.len() <= 1
+    }
+
+    /// Check if `config.end_rules` forbid going out with every card the holders might have.
+    /// Cards only move between their hands from then on, so the game would never end.
+    fn nobody_can_go_out(&self, holders: &[PlayerId]) -> bool {
+        let forbidden = |c: &Card| self.config.end_rules.iter().any(|r| r.forbids_finish(c, self.trump));
+        !self.config.end_rules.is_empty()
+            && holders.iter().all(|&pid| self.candidate_cards(pid).iter().all(forbidden))
     }
 
     pub fn actor_to_move(&self) -> PlayerId {
         match self.phase {
-            Phase::Attacking | Phase::Throwing => {
-                // Current attacker from the attackers list
-                if self.attackers.is_empty() {
-                    self.attacker
-                } else {
-                    self.attackers[self.current_attacker_idx % self.attackers.len()]
-                }
-            }
+            Phase::Attacking | Phase::Throwing => self.current_attacker(),
             Phase::Defending => self.defender,
         }
     }
 
+    /// Ranks that may be added to the trick (including `Rank::Joker` when jokers match anything).
     fn ranks_on_table(&self) -> HashSet<Rank> {
         let mut set = HashSet::new();
+        if self.config.joker_matching == JokerMatching::Any && !self.table.is_empty() {
+            set.insert(Rank::Joker);
+        }
         for p in &self.table {
             set.insert(p.attack.rank());
             if let Some(d) = p.defense {
@@ -609,165 +1670,227 @@ impl GameState {
         self.undefended_pile_count() < self.hand_size(self.defender)
     }
 
-    /// Find who would become the new defender if current defender reflects.
-    /// Returns None if there's no active player to become defender.
-    fn potential_reflect_defender(&self) -> Option<PlayerId> {
-        // The new defender is the next active player from the attackers list
-        if self.attackers.len() > 1 {
-            let candidate = self.attackers[1];
-            if self.is_player_active(candidate) {
-                return Some(candidate);
-            }
+    /// Maximum number of piles allowed in the current trick by the config.
+    fn pile_limit(&self) -> usize {
+        let limit = self.config.max_piles.unwrap_or(usize::MAX);
+        match self.config.first_trick_limit {
+            Some(first) if self.tricks_played == 0 => limit.min(first),
+            _ => limit,
         }
-        // Fallback: find any active player that's not the current defender
-        let num_players = self.num_players();
-        let mut pid = self.defender.next(num_players);
-        for _ in 0..num_players {
-            if pid != self.defender && self.is_player_active(pid) {
-                return Some(pid);
-            }
-            pid = pid.next(num_players);
-        }
-        None
     }
 
-    /// Create a determinized copy of this state from a player's perspective.
-    /// Uses the player's known information (Public + their Private cards).
-    /// Unknown cards are shuffled and reassigned randomly.
-    pub fn determinize(&self, perspective: PlayerId, rng: &mut impl Rng) -> Self {
-        let mut state = self.clone();
+    /// Check if another pile may be added to the table (defender capacity and trick limits).
+    fn can_add_pile(&self) -> bool {
+        self.defender_has_capacity() && self.table.len() < self.pile_limit()
+    }
 
-        // Collect all known cards (visible to perspective)
-        let mut known_cards: HashSet<(Suit, Rank)> = HashSet::new();
+    /// Number of piles that may still be added to the table (defender capacity and trick limits).
+    fn pile_capacity(&self) -> usize {
+        let by_defender = self.hand_size(self.defender).saturating_sub(self.undefended_pile_count());
+        let by_limit = self.pile_limit().saturating_sub(self.table.len());
+        by_defender.min(by_limit)
+    }
 
-        for (hand_idx, hand) in self.hands.iter().enumerate() {
-            for card in hand {
-                // Card is known if Public, or Private in perspective's hand
-                if card.is_public() || (card.is_private() && hand_idx == perspective as usize) {
-                    known_cards.insert((card.suit(), card.rank()));
-                }
-            }
+    /// Check that `new_piles` piles can be added to the trick.
+    fn check_pile_capacity(&self, new_piles: usize) -> Result<(), EngineError> {
+        if self.table.len() + new_piles > self.pile_limit() {
+            return Err(EngineError::PileLimit { limit: self.pile_limit() });
         }
-
-        for card in &self.stock {
-            if card.is_public() {
-                known_cards.insert((card.suit(), card.rank()));
-            }
+        if self.undefended_pile_count() + new_piles > self.hand_size(self.defender) {
+            return Err(EngineError::DefenderAtCapacity { defender: self.defender });
         }
+        Ok(())
+    }
 
-        for card in &self.discard {
-            known_cards.insert((card.suit(), card.rank()));
+    /// Multi-card plays for `pid`: on an empty table, sets of same-rank cards; otherwise sets of
+    /// cards matching the ranks on the table. One play is generated per size from two up to the
+    /// pile capacity (per rank when the ranks must match), made of the lowest cards: non-trumps
+    /// before trumps, then by rank. `apply` still accepts any other valid set. For a hand with
+    /// unknown cards, sets of unseen cards of one rank (up to the hand size) might be played.
+    fn multi_card_groups(&self, pid: PlayerId) -> Vec<Vec<Card>> {
+        let hand = &self.hands[pid.index()];
+        let capacity = self.pile_capacity().min(hand.len());
+        if !self.config.multi_card_attacks || capacity < 2 {
+            return vec![];
         }
 
-        for pile in &self.table {
-            known_cards.insert((pile.attack.suit(), pile.attack.rank()));
-            if let Some(d) = pile.defense {
-                known_cards.insert((d.suit(), d.rank()));
-            }
+        let unknown = hand.iter().any(|c| c.is_unknown());
+        let mut pool = self.candidate_cards(pid);
+        if !self.table.is_empty() {
+            let ranks = self.ranks_on_table();
+            pool.retain(|c| ranks.contains(&c.rank()));
         }
+        // Lowest cards first within each rank
+        let trump = self.trump;
+        pool.sort_by_key(|c| (c.rank(), c.is_trump(trump), c.suit() as u8));
 
-        // Build pool of unknown cards (full deck minus known)
-        let deck = full_deck(self.config.deck_size);
-        let mut unknown_pool: Vec<Card> = deck
-            .into_iter()
-            .filter(|c| !known_cards.contains(&(c.suit(), c.rank())))
-            .map(|c| c.as_public())
-            .collect();
+        let groups: Vec<Vec<Card>> = if self.table.is_empty() || unknown {
+            pool.chunk_by(|a, b| a.rank() == b.rank()).map(<[Card]>::to_vec).collect()
+        } else {
+            pool.sort_by_key(|c| (c.is_trump(trump), c.rank(), c.suit() as u8));
+            vec![pool]
+        };
 
-        // Shuffle the unknown pool
-        for i in (1..unknown_pool.len()).rev() {
-            let j = rng.gen_range(0..=i);
-            unknown_pool.swap(i, j);
+        let mut plays = Vec::new();
+        for group in groups.iter().filter(|g| g.len() >= 2) {
+            plays.extend((2..=capacity.min(group.len())).map(|size| group[..size].to_vec()));
         }
+        plays
+    }
 
-        // Collect positions that need cards from the pool
-        let mut unknown_positions: Vec<(usize, usize)> = Vec::new();
-        let mut unknown_stock_positions: Vec<usize> = Vec::new();
-
-        for (hand_idx, hand) in state.hands.iter().enumerate() {
-            for (card_idx, card) in hand.iter().enumerate() {
-                let is_unknown = card.is_unknown() ||
-                    (card.is_private() && hand_idx != perspective as usize);
-                if is_unknown {
-                    unknown_positions.push((hand_idx, card_idx));
-                }
+    /// Validate a multi-card play against the rank and capacity rules.
+    fn check_multi_card_play(&self, cards: &[Card], same_rank: bool) -> Result<(), EngineError> {
+        let invalid = |reason| EngineError::InvalidMultiCard { reason };
+        if !self.config.multi_card_attacks {
+            return Err(invalid(MultiCardError::Disabled));
+        }
+        let Some(first) = cards.first() else {
+            return Err(invalid(MultiCardError::NoCards));
+        };
+        // Copies per card from the deck itself (jokers alternate red and black, so a deck may hold several)
+        let deck = self.deck();
+        let copies = |c: &Card| deck.iter().filter(|d| d.matches(c)).count();
+        if cards.iter().any(|c| cards.iter().filter(|d| d.matches(c)).count() > copies(c)) {
+            return Err(invalid(MultiCardError::TooManyCopies));
+        }
+        if same_rank && cards.iter().any(|c| c.rank() != first.rank()) {
+            return Err(invalid(MultiCardError::RankMismatch));
+        }
+        if !self.table.is_empty() {
+            let ranks = self.ranks_on_table();
+            if let Some(card) = cards.iter().find(|c| !ranks.contains(&c.rank())) {
+                return Err(EngineError::RankNotOnTable { card: *card });
             }
         }
+        self.check_pile_capacity(cards.len())?;
+        Ok(())
+    }
 
-        for (stock_idx, card) in state.stock.iter().enumerate() {
-            if card.is_unknown() || card.is_private() {
-                unknown_stock_positions.push(stock_idx);
+    /// Move several cards from a player's hand to new piles; the hand is unchanged on error.
+    fn play_to_table(&mut self, pid: PlayerId, cards: &[Card]) -> Result<(), EngineError> {
+        let hand = self.hands[pid.index()].clone();
+        for c in cards {
+            if let Err(e) = self.remove_from_hand(pid, c) {
+                self.hands[pid.index()] = hand;
+                return Err(e);
             }
         }
+        self.table.extend(cards.iter().map(|c| Pile { attack: c.as_public(), defense: None }));
+        Ok(())
+    }
+
+    /// Check if every copy of this trump in the defender's hand has already been shown this trick.
+    fn trump_reflect_used(&self, card: &Card) -> bool {
+        let shown = self.reflected_trumps.iter().filter(|r| r.matches(card)).count();
+        let held = self.hands[self.defender.index()].iter().filter(|c| c.matches(card)).count();
+        shown > 0 && shown >= held
+    }
 
-        // Reassign cards from the shuffled pool
-        let mut pool_idx = 0;
-        for (hand_idx, card_idx) in unknown_positions {
-            if pool_idx < unknown_pool.len() {
-                state.hands[hand_idx][card_idx] = unknown_pool[pool_idx];
-                pool_idx += 1;
+    /// Find the previous active player before the given player who is not on their team.
+    fn prev_opponent(&self, from: PlayerId) -> PlayerId {
+        let mut pid = self.prev_active_player(from);
+        for _ in 1..self.num_players() {
+            if !self.are_teammates(from, pid) {
+                return pid;
             }
+            pid = self.prev_active_player(pid);
         }
+        from // No active opponent found, return original
+    }
 
-        for stock_idx in unknown_stock_positions {
-            if pool_idx < unknown_pool.len() {
-                state.stock[stock_idx] = unknown_pool[pool_idx];
-                pool_idx += 1;
+    /// Possible `to` values of reflect actions: None when the direction is fixed by the config,
+    /// otherwise each distinct neighbouring opponent of the defender.
+    fn reflect_choices(&self) -> Vec<Option<PlayerId>> {
+        if self.config.reflect_direction != ReflectDirection::Either {
+            return vec![None];
+        }
+        let mut choices = vec![];
+        for pid in [self.next_opponent(self.defender), self.prev_opponent(self.defender)] {
+            if pid != self.defender && !choices.contains(&Some(pid)) {
+                choices.push(Some(pid));
             }
         }
+        choices
+    }
 
-        // Make perspective's Private cards Public
-        for card in &mut state.hands[perspective as usize] {
-            if card.is_private() {
-                *card = card.as_public();
-            }
+    /// Find who would become the new defender if the current defender reflects towards `to`
+    /// (None: the configured direction), checking the reflect limits of `config`.
+    /// `new_piles` is the number of piles the reflect adds (1 for a regular reflect, 0 for a trump reflect).
+    fn reflect_target(&self, to: Option<PlayerId>, new_piles: usize) -> Result<PlayerId, EngineError> {
+        if let Some(max) = self.config.max_reflects_per_trick
+            && self.reflects >= max
+        {
+            return Err(EngineError::ReflectNotAllowed { reason: ReflectError::LimitReached });
         }
 
-        state
+        // Not taken from the attackers list, which may be restricted by `config.throw_in`
+        let clockwise = self.next_opponent(self.defender);
+        let counter_clockwise = self.prev_opponent(self.defender);
+        let allowed = match self.config.reflect_direction {
+            ReflectDirection::Clockwise => vec![clockwise],
+            ReflectDirection::CounterClockwise => vec![counter_clockwise],
+            ReflectDirection::Either => vec![clockwise, counter_clockwise],
+        };
+        let new_defender = match to {
+            None => allowed[0],
+            Some(p) if allowed.contains(&p) => p,
+            Some(_) => {
+                return Err(EngineError::ReflectNotAllowed { reason: ReflectError::DirectionNotAllowed });
+            }
+        };
+
+        if new_defender == self.defender {
+            return Err(EngineError::ReflectNotAllowed { reason: ReflectError::NoTarget });
+        }
+        if self.config.no_reflect_to_original_attacker && new_defender == self.original_attacker {
+            return Err(EngineError::ReflectNotAllowed { reason: ReflectError::OriginalAttacker });
+        }
+        if self.table.len() + new_piles > self.pile_limit() {
+            return Err(EngineError::PileLimit { limit: self.pile_limit() });
+        }
+        // The new defender needs enough cards to defend all piles (including a reflected card)
+        if self.hand_size(new_defender) < self.undefended_pile_count() + new_piles {
+            return Err(EngineError::ReflectNotAllowed { reason: ReflectError::TargetCannotDefend });
+        }
+        Ok(new_defender)
     }
 
     /// Get cards a player can use for actions.
     /// If hand contains Unknown cards, returns all unseen cards (any card that could be there).
     /// Otherwise, returns the known cards (Public/Private).
     fn usable_cards(&self, pid: PlayerId) -> Vec<Card> {
-        let hand = &self.hands[pid as usize];
+        // One per identical card in multi-deck games
+        dedup_cards(self.candidate_cards(pid))
+    }
+
+    /// The cards of `usable_cards`, keeping identical copies from several decks.
+    fn candidate_cards(&self, pid: PlayerId) -> Vec<Card> {
+        let hand = &self.hands[pid.index()];
 
         if !hand.iter().any(|c| c.is_unknown()) {
             // No unknown cards - return known cards
-            return hand.clone()
+            return hand.clone();
         }
 
         // Hand contains Unknown cards - return all unseen cards
-        let mut known_cards: HashSet<(Suit, Rank)> = HashSet::new();
+        let mut known_cards: Vec<Card> = Vec::new();
 
         // Cards on the table are known
         for pile in &self.table {
-            known_cards.insert((pile.attack.suit(), pile.attack.rank()));
-            if let Some(d) = pile.defense {
-                known_cards.insert((d.suit(), d.rank()));
-            }
+            known_cards.push(pile.attack);
+            known_cards.extend(pile.defense);
         }
 
         // Cards in discard are known
-        for card in &self.discard {
-            known_cards.insert((card.suit(), card.rank()));
-        }
+        known_cards.extend(&self.discard);
 
         // Public cards in all hands are known
         for hand in &self.hands {
-            for card in hand {
-                if card.is_public() || card.is_private() {
-                    known_cards.insert((card.suit(), card.rank()));
-                }
-            }
+            known_cards.extend(hand.iter().filter(|c| c.is_public() || c.is_private()));
         }
 
         // Public cards in stock are known (trump card)
-        for card in &self.stock {
-            if card.is_public() {
-                known_cards.insert((card.suit(), card.rank()));
-            }
-        }
+        known_cards.extend(self.stock.iter().filter(|c| c.is_public()));
 
         // Start with the player's own known cards (Public/Private in their hand)
         let mut usable: Vec<Card> = hand.iter()
@@ -776,13 +1899,7 @@ impl GameState {
             .collect();
 
         // Add all unseen cards (cards not known to be elsewhere)
-        usable.extend(
-            full_deck(self.config.deck_size)
-                .into_iter()
-                .filter(|c| !known_cards.contains(&(c.suit(), c.rank())))
-                .map(|c| c.as_public())
-        );
-
+        usable.extend(self.unseen(&known_cards));
         usable
     }
 
@@ -793,11 +1910,15 @@ impl GameState {
 
         let current_attacker = self.actor_to_move();
 
-        match self.phase {
+        let mut acts = match self.phase {
             Phase::Attacking => {
                 let mut acts = Vec::new();
                 let usable = self.usable_cards(current_attacker);
 
+                for cards in self.multi_card_groups(current_attacker) {
+                    acts.push(Action::AttackMany { cards });
+                }
+
                 if self.table.is_empty() {
                     // First attack - can play any card
                     for c in usable {
@@ -811,7 +1932,7 @@ impl GameState {
 
                     // Can attack with cards matching ranks on table (if defender has capacity)
                     let ranks = self.ranks_on_table();
-                    if self.defender_has_capacity() {
+                    if self.can_add_pile() {
                         for c in usable {
                             if ranks.contains(&c.rank()) {
                                 acts.push(Action::Attack { card: c });
@@ -831,29 +1952,19 @@ impl GameState {
 
                 let mut acts = Vec::new();
 
-                // Check if reflecting is possible:
+                // Check if reflecting is possible (and towards whom):
                 // - All piles must be undefended
-                // - There must be an active player to become the new defender
+                // - There must be an active player to become the new defender, within the reflect limits
                 // For regular reflect: new defender needs cards for existing piles + the new reflected card pile
                 // For trump reflect: new defender only needs cards for existing piles (no new pile created)
-                let can_reflect_regular = if self.table.iter().all(|p| p.defense.is_none()) {
-                    if let Some(new_defender) = self.potential_reflect_defender() {
-                        self.hand_size(new_defender) >= self.undefended_pile_count() + 1
-                    } else {
-                        false
-                    }
-                } else {
-                    false
-                };
-
-                let can_reflect_trump = if self.table.iter().all(|p| p.defense.is_none()) {
-                    if let Some(new_defender) = self.potential_reflect_defender() {
-                        self.hand_size(new_defender) >= self.undefended_pile_count()
-                    } else {
-                        false
-                    }
+                let (reflect_regular, reflect_trump) = if self.table.iter().all(|p| p.defense.is_none()) {
+                    let choices = self.reflect_choices();
+                    (
+                        choices.iter().copied().filter(|&to| self.reflect_target(to, 1).is_ok()).collect(),
+                        choices.iter().copied().filter(|&to| self.reflect_target(to, 0).is_ok()).collect(),
+                    )
                 } else {
-                    false
+                    (vec![], vec![])
                 };
 
                 for c in usable {
@@ -861,16 +1972,20 @@ impl GameState {
                         acts.push(Action::Defend { pile_index, card: c });
                     }
 
-                    if can_reflect_regular && self.config.reflecting && c.rank() == attack.rank() {
-                        acts.push(Action::Reflect { card: c });
+                    if self.config.reflecting && c.rank() == attack.rank() {
+                        for &to in &reflect_regular {
+                            acts.push(Action::Reflect { card: c, to });
+                        }
                     }
 
-                    if can_reflect_trump && self.config.trump_reflecting
+                    if self.config.trump_reflecting
                         && c.rank() == attack.rank()
-                        && c.suit() == self.trump
-                        && !self.reflected_trumps.iter().any(|r| r.matches(&c))
+                        && c.is_trump(self.trump)
+                        && !self.trump_reflect_used(&c)
                     {
-                        acts.push(Action::ReflectTrump { card: c });
+                        for &to in &reflect_trump {
+                            acts.push(Action::ReflectTrump { card: c, to });
+                        }
                     }
                 }
                 acts.push(Action::Take);
@@ -883,7 +1998,7 @@ impl GameState {
                 let usable = self.usable_cards(current_attacker);
 
                 // Can only throw if defender has capacity (undefended piles < defender's hand)
-                if self.defender_has_capacity() {
+                if self.can_add_pile() {
                     for c in usable {
                         if ranks.contains(&c.rank()) {
                             acts.push(Action::Throw { card: Some(c) });
@@ -891,24 +2006,141 @@ impl GameState {
                     }
                 }
 
+                for cards in self.multi_card_groups(current_attacker) {
+                    acts.push(Action::ThrowMany { cards });
+                }
+
                 acts
             }
+        };
+
+        if self.can_exchange_trump(current_attacker) {
+            acts.push(Action::ExchangeTrump);
+        }
+
+        if !self.config.end_rules.is_empty() {
+            acts.retain(|a| self.forbidden_finish(current_attacker, a).is_none());
+            // An attacker who may not open with their last card passes the attack on
+            if self.phase == Phase::Attacking && self.table.is_empty() && acts.is_empty() {
+                acts.push(Action::PassAttack);
+            }
         }
+        acts
     }
 
     pub fn apply(&mut self, action: &Action) -> Result<(), EngineError> {
-        let current_attacker = self.actor_to_move();
+        let actor = match (self.phase, action) {
+            // Cards added while the defender defends (`TurnModel::Simultaneous`)
+            (Phase::Defending, Action::Attack { .. } | Action::AttackMany { .. }) => self.current_attacker(),
+            _ => self.actor_to_move(),
+        };
+        if let Some(card) = self.forbidden_finish(actor, action) {
+            return Err(EngineError::ForbiddenFinish { card });
+        }
+        self.apply_action(action)?;
+        self.record_finishers();
+        Ok(())
+    }
+
+    /// Players who may act right now: the player to move, followed (in turn order) by every
+    /// other attacker holding cards while an open trick is being added to, since at a real
+    /// table anyone eligible may throw in at any moment.
+    pub fn eligible_players(&self) -> Vec<PlayerId> {
+        let to_move = self.actor_to_move();
+        let mut players = vec![to_move];
+        let adding = match self.phase {
+            Phase::Attacking => !self.table.is_empty(),
+            Phase::Throwing => true,
+            Phase::Defending => self.is_simultaneous() && self.can_add_pile(),
+        };
+        if adding && !self.attackers.is_empty() {
+            let n = self.attackers.len();
+            let first = if self.phase == Phase::Defending { 0 } else { 1 };
+            players.extend(
+                (first..n)
+                    .map(|i| self.attackers[(self.current_attacker_idx + i) % n])
+                    .filter(|&pid| pid != to_move && self.hand_size(pid) > 0)
+                    .filter(|pid| !self.done_attackers.contains(pid)),
+            );
+        }
+        players
+    }
+
+    /// Apply an action submitted by `player`, rejecting it if `player` may not act now.
+    /// Eligible attackers other than the player to move may only add cards to the trick (or, with
+    /// `TurnModel::Simultaneous`, vote that they are done). Adding cards out of turn takes over the
+    /// turn, and every attacker gets another chance to add cards after them.
+    pub fn apply_as(&mut self, player: PlayerId, action: &Action) -> Result<(), EngineError> {
+        let to_move = self.actor_to_move();
+        if player == to_move {
+            return self.apply(action);
+        }
+        let adds_cards = matches!(
+            action,
+            Action::Attack { .. } | Action::AttackMany { .. } | Action::Throw { card: Some(_) } | Action::ThrowMany { .. }
+        );
+        let votes_done = self.is_simultaneous()
+            && matches!(
+                (self.phase, action),
+                (Phase::Attacking, Action::PassAttack) | (Phase::Throwing, Action::Throw { card: None })
+            );
+        if !(adds_cards || votes_done) || !self.eligible_players().contains(&player) {
+            return Err(EngineError::NotYourTurn { player, to_move });
+        }
+        if votes_done {
+            if self.phase == Phase::Attacking && self.open_pile_index().is_some() {
+                return Err(EngineError::PilesUndefended);
+            }
+            // The trick goes on unless the player to move went out and everyone else is done
+            if self.vote_done(player) {
+                match self.phase {
+                    Phase::Throwing => self.finish_taken_trick(),
+                    _ => self.finish_defended_trick(),
+                }
+                self.record_finishers();
+            }
+            return Ok(());
+        }
+
+        // Try on a copy, so a rejected action leaves the turn with the player to move
+        let mut next = self.clone();
+        next.current_attacker_idx = next.attackers.iter().position(|&p| p == player).unwrap_or(0);
+        if next.phase == Phase::Throwing {
+            next.throw_start_idx = next.current_attacker_idx;
+        }
+        next.apply(action)?;
+        *self = next;
+        Ok(())
+    }
+
+    /// Decide which of several actions submitted at the same moment is applied: the first one
+    /// (in order of `eligible_players`, then submission order) that `apply_as` would accept.
+    /// Returns its index in `submissions`, or None if none of them can be applied.
+    pub fn resolve_race(&self, submissions: &[(PlayerId, Action)]) -> Option<usize> {
+        let eligible = self.eligible_players();
+        let mut order: Vec<usize> = (0..submissions.len())
+            .filter(|&i| eligible.contains(&submissions[i].0))
+            .collect();
+        order.sort_by_key(|&i| eligible.iter().position(|&p| p == submissions[i].0));
+        order.into_iter().find(|&i| {
+            let (player, action) = &submissions[i];
+            self.clone().apply_as(*player, action).is_ok()
+        })
+    }
+
+    fn apply_action(&mut self, action: &Action) -> Result<(), EngineError> {
+        let current_attacker = self.current_attacker();
+        let simultaneous = self.is_simultaneous();
 
         match (self.phase, action) {
-            (Phase::Attacking, Action::Attack { card }) => {
+            (Phase::Attacking, Action::Attack { card })
+            | (Phase::Defending, Action::Attack { card }) if self.phase == Phase::Attacking || simultaneous => {
                 let card = *card;
-                if self.hand_size(self.defender) == 0 {
-                    return Err(EngineError::IllegalMove("too many piles".into()));
-                }
+                self.check_pile_capacity(1)?;
                 if !self.table.is_empty() {
                     let ranks = self.ranks_on_table();
                     if !ranks.contains(&card.rank()) {
-                        return Err(EngineError::IllegalMove("attack rank not on table".into()));
+                        return Err(EngineError::RankNotOnTable { card });
                     }
                 }
 
@@ -917,14 +2149,29 @@ impl GameState {
                 self.table.push(Pile { attack: card.as_public(), defense: None });
                 // Track who last played an attack card
                 self.last_played_attacker = current_attacker;
+                self.done_attackers.clear();
+                self.phase = Phase::Defending;
+                Ok(())
+            }
+            (Phase::Attacking, Action::AttackMany { cards })
+            | (Phase::Defending, Action::AttackMany { cards }) if self.phase == Phase::Attacking || simultaneous => {
+                self.check_multi_card_play(cards, self.table.is_empty())?;
+                self.play_to_table(current_attacker, cards)?;
+                self.last_played_attacker = current_attacker;
+                self.done_attackers.clear();
                 self.phase = Phase::Defending;
                 Ok(())
             }
             (Phase::Attacking, Action::PassAttack) => {
                 if self.open_pile_index().is_some() {
-                    return Err(EngineError::IllegalMove(
-                        "cannot pass while there are undefended piles".into(),
-                    ));
+                    return Err(EngineError::PilesUndefended);
+                }
+
+                if simultaneous {
+                    if self.vote_done(current_attacker) {
+                        self.finish_defended_trick();
+                    }
+                    return Ok(());
                 }
 
                 // Move to next attacker
@@ -940,35 +2187,18 @@ impl GameState {
                 }
 
                 // All attackers have passed - successful defense
-                // Discard the table (cards stay public in discard)
-                for p in self.table.drain(..) {
-                    self.discard.push(p.attack);
-                    if let Some(d) = p.defense {
-                        self.discard.push(d);
-                    }
-                }
-
-                // Refill hands in draw order (attackers first, then defender)
-                self.refill_hands();
-
-                // Start new trick with defender as main attacker
-                self.new_trick(self.defender);
+                self.finish_defended_trick();
                 Ok(())
             }
             (Phase::Defending, Action::Defend { pile_index, card }) => {
                 let pile_index = *pile_index;
                 let card = *card;
-                if pile_index >= self.table.len() {
-                    return Err(EngineError::IllegalMove("bad pile index".into()));
-                }
-                if self.table[pile_index].defense.is_some() {
-                    return Err(EngineError::IllegalMove("pile already defended".into()));
+                if self.table.get(pile_index).is_none_or(|p| p.defense.is_some()) {
+                    return Err(EngineError::PileNotOpen { pile: pile_index });
                 }
                 let attack = self.table[pile_index].attack;
                 if !card.beats(&attack, self.trump) {
-                    return Err(EngineError::IllegalMove(
-                        "defense card does not beat attack".into(),
-                    ));
+                    return Err(EngineError::DoesNotBeat { pile: pile_index, card, attack });
                 }
 
                 self.remove_from_hand(self.defender, &card)?;
@@ -976,6 +2206,10 @@ impl GameState {
 
                 if self.open_pile_index().is_none() {
                     self.phase = Phase::Attacking;
+                    // An attacker who went out is done adding cards
+                    if simultaneous && self.hand_size(current_attacker) == 0 && self.vote_done(current_attacker) {
+                        self.finish_defended_trick();
+                    }
                 }
 
                 Ok(())
@@ -983,42 +2217,31 @@ impl GameState {
             (Phase::Defending, Action::Take) => {
                 // Remember which attacker starts the throwing phase
                 self.throw_start_idx = self.current_attacker_idx;
+                self.done_attackers.clear();
                 self.phase = Phase::Throwing;
+                if simultaneous && self.hand_size(current_attacker) == 0 && self.vote_done(current_attacker) {
+                    self.finish_taken_trick();
+                }
                 Ok(())
             }
-            (Phase::Defending, Action::Reflect { card }) => {
+            (Phase::Defending, Action::Reflect { card, to }) => {
                 let card = *card;
                 if !self.config.reflecting {
-                    return Err(EngineError::IllegalMove("reflecting not enabled".into()));
+                    return Err(EngineError::ReflectNotAllowed { reason: ReflectError::Disabled });
                 }
                 if self.table.iter().any(|p| p.defense.is_some()) {
-                    return Err(EngineError::IllegalMove(
-                        "cannot reflect after defending a pile".into(),
-                    ));
+                    return Err(EngineError::ReflectNotAllowed { reason: ReflectError::AlreadyDefended });
                 }
                 let Some(pile_index) = self.open_pile_index() else {
-                    return Err(EngineError::IllegalMove("no open pile to reflect".into()));
+                    return Err(EngineError::ReflectNotAllowed { reason: ReflectError::NoOpenPile });
                 };
                 let attack = self.table[pile_index].attack;
                 if card.rank() != attack.rank() {
-                    return Err(EngineError::IllegalMove(
-                        "reflect card must have same rank as attack".into(),
-                    ));
+                    return Err(EngineError::ReflectNotAllowed { reason: ReflectError::RankMismatch });
                 }
 
-                // Find new defender - must be an active player
-                let Some(new_defender) = self.potential_reflect_defender() else {
-                    return Err(EngineError::IllegalMove(
-                        "no active player to become defender".into(),
-                    ));
-                };
-
-                // Check if new defender has enough cards to defend all piles (including the reflected card)
-                if self.hand_size(new_defender) < self.undefended_pile_count() + 1 {
-                    return Err(EngineError::IllegalMove(
-                        "new defender does not have enough cards to defend all piles".into(),
-                    ));
-                }
+                // Find new defender - must be an active player who can defend all piles (including the reflected card)
+                let new_defender = self.reflect_target(*to, 1)?;
 
                 self.remove_from_hand(self.defender, &card)?;
                 self.table.push(Pile { attack: card.as_public(), defense: None });
@@ -1027,66 +2250,49 @@ impl GameState {
                 let old_defender = self.defender;
                 self.attacker = old_defender;
                 self.defender = new_defender;
+                self.reflects += 1;
+                self.done_attackers.clear();
 
-                // Rebuild attackers list
+                // Rebuild attackers list (a reflector who played their last card is no longer in it)
                 self.attackers = self.build_attackers(self.attacker);
                 self.current_attacker_idx = 0;
-                self.last_played_attacker = self.attacker;
+                self.last_played_attacker = self.attackers.first().copied().unwrap_or(self.attacker);
 
                 self.phase = Phase::Defending;
                 Ok(())
             }
-            (Phase::Defending, Action::ReflectTrump { card }) => {
+            (Phase::Defending, Action::ReflectTrump { card, to }) => {
                 let card = *card;
                 if !self.config.trump_reflecting {
-                    return Err(EngineError::IllegalMove("trump reflecting not enabled".into()));
+                    return Err(EngineError::ReflectNotAllowed { reason: ReflectError::TrumpDisabled });
                 }
                 if self.table.iter().any(|p| p.defense.is_some()) {
-                    return Err(EngineError::IllegalMove(
-                        "cannot reflect after defending a pile".into(),
-                    ));
+                    return Err(EngineError::ReflectNotAllowed { reason: ReflectError::AlreadyDefended });
                 }
                 let Some(pile_index) = self.open_pile_index() else {
-                    return Err(EngineError::IllegalMove("no open pile to reflect".into()));
+                    return Err(EngineError::ReflectNotAllowed { reason: ReflectError::NoOpenPile });
                 };
                 let attack = self.table[pile_index].attack;
                 if card.rank() != attack.rank() {
-                    return Err(EngineError::IllegalMove(
-                        "reflect card must have same rank as attack".into(),
-                    ));
+                    return Err(EngineError::ReflectNotAllowed { reason: ReflectError::RankMismatch });
                 }
-                if card.suit() != self.trump {
-                    return Err(EngineError::IllegalMove(
-                        "trump reflect requires a trump card".into(),
-                    ));
+                if !card.is_trump(self.trump) {
+                    return Err(EngineError::ReflectNotAllowed { reason: ReflectError::NotTrump });
                 }
-                if self.reflected_trumps.iter().any(|r| r.matches(&card)) {
-                    return Err(EngineError::IllegalMove(
-                        "this trump has already been used for reflecting this turn".into(),
-                    ));
+                if self.trump_reflect_used(&card) {
+                    return Err(EngineError::ReflectNotAllowed { reason: ReflectError::TrumpAlreadyShown });
                 }
 
-                // Find new defender - must be an active player
-                let Some(new_defender) = self.potential_reflect_defender() else {
-                    return Err(EngineError::IllegalMove(
-                        "no active player to become defender".into(),
-                    ));
-                };
-
-                // Check if new defender has enough cards to defend all piles
+                // Find new defender - must be an active player who can defend all piles
                 // Trump reflecting doesn't create a new pile, so only existing piles need to be defended
-                if self.hand_size(new_defender) < self.undefended_pile_count() {
-                    return Err(EngineError::IllegalMove(
-                        "new defender does not have enough cards to defend all piles".into(),
-                    ));
-                }
+                let new_defender = self.reflect_target(*to, 0)?;
 
                 // Card stays in hand but becomes public, marked as used
                 self.reflected_trumps.push(card.as_public());
 
                 // Make the card public in hand
-                if let Some(c) = self.hands[self.defender as usize].iter_mut()
-                    .find(|c| c.matches(&card))
+                if let Some(c) = self.hands[self.defender.index()].iter_mut()
+                    .find(|c| c.matches(&card) && !c.is_public())
                 {
                     *c = c.as_public();
                 }
@@ -1095,6 +2301,8 @@ impl GameState {
                 let old_defender = self.defender;
                 self.attacker = old_defender;
                 self.defender = new_defender;
+                self.reflects += 1;
+                self.done_attackers.clear();
 
                 // Rebuild attackers list
                 self.attackers = self.build_attackers(self.attacker);
@@ -1104,17 +2312,29 @@ impl GameState {
                 self.phase = Phase::Defending;
                 Ok(())
             }
+            (Phase::Throwing, Action::ThrowMany { cards }) => {
+                self.check_multi_card_play(cards, false)?;
+                self.play_to_table(current_attacker, cards)?;
+                self.done_attackers.clear();
+                Ok(())
+            }
             (Phase::Throwing, Action::Throw { card }) => {
                 if let Some(c) = card {
                     let ranks = self.ranks_on_table();
                     if !ranks.contains(&c.rank()) {
-                        return Err(EngineError::IllegalMove("thrown rank not on table".into()));
-                    }
-                    if self.hand_size(self.defender) == 0 {
-                        return Err(EngineError::IllegalMove("defender has no capacity".into()));
+                        return Err(EngineError::RankNotOnTable { card: *c });
                     }
+                    self.check_pile_capacity(1)?;
                     self.remove_from_hand(current_attacker, c)?;
                     self.table.push(Pile { attack: c.as_public(), defense: None });
+                    self.done_attackers.clear();
+                    return Ok(());
+                }
+
+                if simultaneous {
+                    if self.vote_done(current_attacker) {
+                        self.finish_taken_trick();
+                    }
                     return Ok(());
                 }
 
@@ -1129,31 +2349,25 @@ impl GameState {
                 }
 
                 // All attackers done throwing - defender takes everything
-                let taken: Vec<Card> = self.table.drain(..)
-                    .flat_map(|p| {
-                        let mut cards = vec![p.attack];
-                        if let Some(d) = p.defense {
-                            cards.push(d);
-                        }
-                        cards
-                    })
-                    .collect();
-
-                // Cards taken are public (they were on the table)
-                self.hands[self.defender as usize].extend(taken);
-
-                // Refill hands in draw order (attackers first, then defender)
-                self.refill_hands();
+                self.finish_taken_trick();
+                Ok(())
+            }
+            (_, Action::ExchangeTrump) => {
+                let pid = self.actor_to_move();
+                if !self.can_exchange_trump(pid) {
+                    return Err(EngineError::TrumpExchangeNotAllowed);
+                }
 
-                // Start new trick - main attacker is player after the defender
-                let num_players = self.num_players();
-                let new_main_attacker = self.defender.next(num_players);
-                self.new_trick(new_main_attacker);
+                let lowest = self.lowest_trump();
+                self.remove_from_hand(pid, &lowest)?;
+                // Everyone sees both cards: the face-up trump goes to the hand, the lowest trump is the new bottom card
+                let face_up = self.stock[0];
+                self.stock[0] = lowest;
+                self.trump_card = Some(lowest);
+                self.hands[pid.index()].push(face_up.as_public());
                 Ok(())
             }
-            (_, a) => Err(EngineError::IllegalMove(format!(
-                "action not allowed in this phase: {a:?}"
-            ))),
+            (phase, a) => Err(EngineError::WrongPhase { phase, action: a.kind() }),
         }
     }
 
@@ -1161,7 +2375,7 @@ impl GameState {
     /// If the card isn't found but the hand has Unknown cards, removes one Unknown
     /// (the opponent "reveals" that their unknown card was this specific card).
     fn remove_from_hand(&mut self, pid: PlayerId, card: &Card) -> Result<(), EngineError> {
-        let hand = &mut self.hands[pid as usize];
+        let hand = &mut self.hands[pid.index()];
         // First try exact match
         if let Some(i) = hand.iter().position(|c| c.matches(card)) {
             hand.swap_remove(i);
@@ -1172,18 +2386,71 @@ impl GameState {
             hand.swap_remove(i);
             return Ok(());
         }
-        Err(EngineError::IllegalMove(format!("card not in {pid:?}'s hand")))
+        Err(EngineError::CardNotInHand { player: pid, card: *card })
+    }
+
+    /// All cards in play (see `GameConfig::deck`). Games are only created with a valid config.
+    fn deck(&self) -> Vec<Card> {
+        self.config.deck().expect("game config validated on creation")
+    }
+
+    /// Cards in play that are not among `known` (see `unseen_cards`).
+    fn unseen(&self, known: &[Card]) -> Vec<Card> {
+        remove_known(self.deck(), known)
+    }
+
+    /// The lowest trump in the deck (e.g. the six of trumps in a 36-card deck).
+    fn lowest_trump(&self) -> Card {
+        let rank = self.deck()
+            .iter()
+            .filter(|c| !c.is_joker())
+            .map(|c| c.rank())
+            .min()
+            .unwrap_or(Rank::Six);
+        Card::public(self.trump, rank)
+    }
+
+    /// Check if a player may swap the lowest trump for the face-up trump at the bottom of the stock.
+    /// Once the face-up trump is the last card of the stock, it is simply drawn.
+    fn can_exchange_trump(&self, pid: PlayerId) -> bool {
+        if !self.config.trump_exchange || self.stock.len() < 2 {
+            return false;
+        }
+        let lowest = self.lowest_trump();
+        let face_up_available = self.stock.first().is_some_and(|c| c.is_public() && !c.matches(&lowest));
+        face_up_available && self.usable_cards(pid).iter().any(|c| c.matches(&lowest))
+    }
+
+    /// Record players who went out since the last trick.
+    /// Exits are settled when a trick ends (or the game ends mid-trick), so players
+    /// who empty their hands during the same trick share a placement.
+    fn record_finishers(&mut self) {
+        if !self.stock.is_empty() || (!self.table.is_empty() && !self.is_terminal()) {
+            return;
+        }
+
+        let group: Vec<PlayerId> = (0..self.num_players())
+            .map(PlayerId::from_index)
+            .filter(|&pid| self.hand_size(pid) == 0 && !self.finished.iter().flatten().any(|&p| p == pid))
+            .collect();
+        if !group.is_empty() {
+            self.finished.push(group);
+        }
     }
 
     /// Refill hands after a round ends by drawing from stock.
-    /// Draw order: attackers first (in order), then defender.
+    /// Draw order: attackers first (in seat order from the main attacker), then defender.
     fn refill_hands(&mut self) {
-        // Build draw order: attackers first, then defender
-        let mut draw_order: Vec<PlayerId> = self.attackers.clone();
+        // Build draw order: everyone but the defender (including players not allowed to throw in), then defender
+        let num_players = self.num_players();
+        let mut draw_order: Vec<PlayerId> = (0..num_players)
+            .map(|i| PlayerId::from_index((self.attacker.index() + i) % num_players))
+            .filter(|&pid| pid != self.defender)
+            .collect();
         draw_order.push(self.defender);
 
         for pid in draw_order {
-            while self.hand_size(pid) < 6 && !self.stock.is_empty() {
+            while self.hand_size(pid) < self.config.hand_size && !self.stock.is_empty() {
                 if let Some(card) = self.stock.pop() {
                     // Drawn cards remain private (or become private if they were in stock)
                     // Exception: trump card (first in stock) is already public
@@ -1193,42 +2460,217 @@ impl GameState {
                     } else {
                         card.as_private()
                     };
-                    self.hands[pid as usize].push(card);
+                    self.hands[pid.index()].push(card);
+                    // The drawn card may break a proven void
+                    self.voids.retain(|v| v.player != pid);
                 }
             }
         }
     }
 }
 
-pub fn full_deck(size: usize) -> Vec<Card> {
-    let suits = [Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades];
-    let ranks = match size {
-        32 => vec![Rank::Seven, Rank::Eight, Rank::Nine, Rank::Ten, Rank::Jack, Rank::Queen, Rank::King, Rank::Ace],
-        36 => vec![Rank::Six, Rank::Seven, Rank::Eight, Rank::Nine, Rank::Ten, Rank::Jack, Rank::Queen, Rank::King, Rank::Ace],
-        40 => vec![Rank::Five, Rank::Six, Rank::Seven, Rank::Eight, Rank::Nine, Rank::Ten, Rank::Jack, Rank::Queen, Rank::King, Rank::Ace],
-        44 => vec![Rank::Four, Rank::Five, Rank::Six, Rank::Seven, Rank::Eight, Rank::Nine, Rank::Ten, Rank::Jack, Rank::Queen, Rank::King, Rank::Ace],
-        48 => vec![Rank::Three, Rank::Four, Rank::Five, Rank::Six, Rank::Seven, Rank::Eight, Rank::Nine, Rank::Ten, Rank::Jack, Rank::Queen, Rank::King, Rank::Ace],
-        52 => vec![Rank::Two, Rank::Three, Rank::Four, Rank::Five, Rank::Six, Rank::Seven, Rank::Eight, Rank::Nine, Rank::Ten, Rank::Jack, Rank::Queen, Rank::King, Rank::Ace],
-        _ => vec![Rank::Six, Rank::Seven, Rank::Eight, Rank::Nine, Rank::Ten, Rank::Jack, Rank::Queen, Rank::King, Rank::Ace], // default to 36
-    };
+const ALL_SUITS: [Suit; 4] = [Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades];
+
+const ALL_RANKS: [Rank; 13] = [
+    Rank::Two, Rank::Three, Rank::Four, Rank::Five, Rank::Six, Rank::Seven, Rank::Eight,
+    Rank::Nine, Rank::Ten, Rank::Jack, Rank::Queen, Rank::King, Rank::Ace,
+];
+
+/// The standard deck of `size` cards (private), see `DeckSpec::standard`.
+pub fn full_deck(size: usize) -> Result<Vec<Card>, EngineError> {
+    Ok(DeckSpec::standard(size)?.cards())
+}
+
+/// Cards of the full deck(s) that are not among `known`, as public cards.
+/// Identical cards are counted, so with several decks one known copy leaves the others unseen.
+pub fn unseen_cards(config: &GameConfig, known: &[Card]) -> Result<Vec<Card>, EngineError> {
+    Ok(remove_known(config.deck()?, known))
+}
 
-    let mut out = Vec::with_capacity(size);
-    for s in suits {
-        for r in &ranks {
-            out.push(Card::private(s, *r));
+/// The cards of `deck` without those among `known`, as public cards.
+fn remove_known(deck: Vec<Card>, known: &[Card]) -> Vec<Card> {
+    let mut known_counts: HashMap<(Suit, Rank), usize> = HashMap::new();
+    for c in known.iter().filter(|c| !c.is_unknown()) {
+        *known_counts.entry((c.suit(), c.rank())).or_default() += 1;
+    }
+    deck
+        .into_iter()
+        .filter(|c| match known_counts.get_mut(&(c.suit(), c.rank())) {
+            Some(n) if *n > 0 => {
+                *n -= 1;
+                false
+            }
+            _ => true,
+        })
+        .map(|c| c.as_public())
+        .collect()
+}
+
+/// Remove repeated cards (same suit and rank), keeping the first of each.
+fn dedup_cards(cards: Vec<Card>) -> Vec<Card> {
+    let mut out: Vec<Card> = Vec::with_capacity(cards.len());
+    for c in cards {
+        if !out.iter().any(|o| o.matches(&c)) {
+            out.push(c);
         }
     }
     out
 }
 
+/// Final result of a finished game.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Outcome {
+    /// One player is left holding cards.
+    Durak {
+        durak: PlayerId,
+        /// Players who went out, in finishing order (groups share a placement).
+        placements: Vec<Vec<PlayerId>>,
+        /// Special results from `config.end_rules` (e.g. epaulettes).
+        #[serde(default)]
+        specials: Vec<SpecialOutcome>,
+    },
+    /// The last players went out together, or the players left may not go out with any of their
+    /// cards under `config.end_rules`. Nobody is durak.
+    Draw {
+        /// Players who went out, in finishing order (groups share a placement).
+        placements: Vec<Vec<PlayerId>>,
+    },
+    /// Several members of one team are left holding cards (they never attack each other).
+    TeamDurak {
+        team: u8,
+        players: Vec<PlayerId>,
+        /// Players who went out, in finishing order (groups share a placement).
+        placements: Vec<Vec<PlayerId>>,
+        /// Special results from `config.end_rules` (e.g. epaulettes).
+        #[serde(default)]
+        specials: Vec<SpecialOutcome>,
+    },
+}
+
+/// Special end-of-game result reported by an `EndRule`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SpecialOutcome {
+    /// The player lost holding a pair (or more) of `rank`.
+    Epaulettes { player: PlayerId, rank: Rank },
+}
+
+impl Outcome {
+    pub fn placements(&self) -> &[Vec<PlayerId>] {
+        match self {
+            Outcome::Durak { placements, .. }
+            | Outcome::Draw { placements }
+            | Outcome::TeamDurak { placements, .. } => placements,
+        }
+    }
+
+    pub fn durak(&self) -> Option<PlayerId> {
+        match self {
+            Outcome::Durak { durak, .. } => Some(*durak),
+            Outcome::Draw { .. } | Outcome::TeamDurak { .. } => None,
+        }
+    }
+
+    pub fn specials(&self) -> &[SpecialOutcome] {
+        match self {
+            Outcome::Durak { specials, .. } | Outcome::TeamDurak { specials, .. } => specials,
+            Outcome::Draw { .. } => &[],
+        }
+    }
+
+    /// Players left holding cards.
+    pub fn losers(&self) -> Vec<PlayerId> {
+        match self {
+            Outcome::Durak { durak, .. } => vec![*durak],
+            Outcome::Draw { .. } => vec![],
+            Outcome::TeamDurak { players, .. } => players.clone(),
+        }
+    }
+
+    /// Zero-based placement of a player (0 = first out).
+    /// Players in the same group share the placement of the best among them;
+    /// the losers are placed after everyone else.
+    pub fn placement(&self, pid: PlayerId) -> Option<usize> {
+        let mut ahead = 0;
+        for group in self.placements() {
+            if group.contains(&pid) {
+                return Some(ahead);
+            }
+            ahead += group.len();
+        }
+        self.losers().contains(&pid).then_some(ahead)
+    }
+
+    /// Placement as a score in [0, 1]: 1 for finishing first alone, 0 for the durak.
+    /// Tied players get the average of the places they share.
+    pub fn placement_score(&self, pid: PlayerId, num_players: usize) -> f64 {
+        if num_players < 2 {
+            return 0.0;
+        }
+        let mut ahead = 0;
+        for group in self.placements() {
+            if group.contains(&pid) {
+                let avg_place = ahead as f64 + (group.len() - 1) as f64 / 2.0;
+                return 1.0 - avg_place / (num_players - 1) as f64;
+            }
+            ahead += group.len();
+        }
+        0.0
+    }
+}
+
+/// What MCTS optimizes for the perspective player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
+#[serde(rename_all = "snake_case")]
+pub enum MCTSObjective {
+    /// 1 if someone else ends up durak, 0 otherwise (draws count as losses).
+    /// In team games: 1 if another team loses.
+    #[default]
+    NotDurak,
+    /// Expected placement, see `Outcome::placement_score`.
+    /// In team games: the average placement of the team.
+    Placement,
+}
+
+impl MCTSObjective {
+    /// Reward of a rollout end state for `perspective`. Unfinished games score 0.
+    pub fn reward(self, state: &GameState, perspective: PlayerId) -> f64 {
+        let Some(outcome) = state.outcome() else {
+            return 0.0;
+        };
+        let team = match state.team_of(perspective) {
+            Some(t) => (0..state.num_players())
+                .map(PlayerId::from_index)
+                .filter(|&pid| state.team_of(pid) == Some(t))
+                .collect(),
+            None => vec![perspective],
+        };
+        match self {
+            MCTSObjective::NotDurak => {
+                let losers = outcome.losers();
+                if !losers.is_empty() && !losers.iter().any(|p| team.contains(p)) { 1.0 } else { 0.0 }
+            }
+            MCTSObjective::Placement => {
+                let total: f64 = team.iter().map(|&pid| outcome.placement_score(pid, state.num_players())).sum();
+                total / team.len() as f64
+            }
+        }
+    }
+}
+
 /// Result of rollout-based evaluation for a single action.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
 pub struct RolloutActionResult {
     /// The action evaluated.
     pub action: Action,
     /// Number of visits (rollouts) for this action.
     pub visits: u32,
-    /// Score: wins / visits. Range [0, 1].
+    /// Score: reward / visits. Range [0, 1].
     pub score: f32,
 }
 
@@ -1236,24 +2678,24 @@ pub struct RolloutActionResult {
 // UCT-MCTS Tree Search (non-recursive, compact)
 // ============================================================================
 
-/// MCTS tree node: wins/visits stats + children keyed by action.
+/// MCTS tree node: reward/visits stats + children keyed by action.
 #[derive(Debug, Clone, Default)]
 struct MCTSNode {
-    wins: u32,
+    reward: f64,
     visits: u32,
     children: HashMap<Action, MCTSNode>,
     unexplored: Vec<Action>,
 }
 
 impl MCTSNode {
-    /// UCT selection: w/n + C * sqrt(ln(N)/n)
+    /// UCT selection: r/n + C * sqrt(ln(N)/n)
     fn uct_select(&self, c: f64) -> Option<&Action> {
         let ln_n = (self.visits as f64).ln();
         self.children.iter()
             .max_by(|(_, a), (_, b)| {
                 let score = |node: &MCTSNode| {
                     if node.visits == 0 { f64::INFINITY }
-                    else { node.wins as f64 / node.visits as f64 + c * (ln_n / node.visits as f64).sqrt() }
+                    else { node.reward / node.visits as f64 + c * (ln_n / node.visits as f64).sqrt() }
                 };
                 score(a).partial_cmp(&score(b)).unwrap_or(std::cmp::Ordering::Equal)
             })
@@ -1263,6 +2705,7 @@ impl MCTSNode {
 
 /// Result of UCT-MCTS evaluation.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
 pub struct MCTSEvalAll {
     pub actions: Vec<RolloutActionResult>,
     pub total_rollouts: u32,
@@ -1277,6 +2720,7 @@ pub fn mcts_evaluate_actions(
     rollouts: u32,
     max_depth: u32,
     c: f64,
+    objective: MCTSObjective,
 ) -> MCTSEvalAll {
     let mut rng = StdRng::seed_from_u64(seed);
     let mut root = MCTSNode { unexplored: state.legal_actions(), ..Default::default() };
@@ -1310,23 +2754,23 @@ pub fn mcts_evaluate_actions(
             let _ = s.apply(&acts[idx]);
             depth += 1;
         }
-        let win = matches!(s.durak(), Some(d) if d != perspective);
+        let reward = objective.reward(&s, perspective);
 
         // Backpropagation: update stats along path
         root.visits += 1;
-        if win { root.wins += 1; }
+        root.reward += reward;
         let mut node = &mut root;
         for action in &path {
             node = node.children.get_mut(action).unwrap();
             node.visits += 1;
-            if win { node.wins += 1; }
+            node.reward += reward;
         }
     }
 
     // Extract and sort results
     let mut results: Vec<_> = root.children.iter().map(|(action, n)| {
         let score = if n.visits > 0 {
-            n.wins as f32 / n.visits as f32
+            (n.reward / n.visits as f64) as f32
         } else {
             0.0
         };
@@ -1352,3 +2796,697 @@ pub fn pick_random_action(state: &GameState, seed: u64) -> Option<Action> {
     let mut rng = StdRng::seed_from_u64(seed);
     Some(actions[rng.gen_range(0..actions.len())].clone())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{card, cards, config, endgame, pid};
+
+    #[test]
+    fn unsupported_decks_are_errors() {
+        let config = GameConfig { deck_size: 37, ..GameConfig::default() };
+        assert!(matches!(config.deck(), Err(EngineError::InvalidSetup { .. })));
+        assert!(config.total_cards().is_err());
+        assert!(unseen_cards(&config, &[]).is_err());
+        assert!(GameState::new_computer_game(0, config).is_err());
+
+        let config = GameConfig { decks: 2, ..GameConfig::default() };
+        assert_eq!(config.deck().unwrap().len(), 72);
+        assert_eq!(unseen_cards(&config, &[card("6H")]).unwrap().len(), 71);
+    }
+
+    #[test]
+    fn players_out_in_the_same_trick_share_a_placement() {
+        let mut state = endgame(config(3), &["7C", "8C", "9D TD"], 0);
+        state.apply(&Action::Attack { card: card("7C") }).unwrap();
+        state.apply(&Action::Defend { pile_index: 0, card: card("8C") }).unwrap();
+
+        assert!(state.is_terminal());
+        assert_eq!(state.finished, vec![vec![pid(0), pid(1)]]);
+        let outcome = state.outcome().unwrap();
+        assert_eq!(outcome.durak(), Some(pid(2)));
+        assert_eq!(outcome.placement(pid(0)), Some(0));
+        assert_eq!(outcome.placement(pid(1)), Some(0));
+        assert_eq!(outcome.placement(pid(2)), Some(2));
+        assert_eq!(outcome.placement_score(pid(0), 3), 0.75);
+        assert_eq!(outcome.placement_score(pid(2), 3), 0.0);
+    }
+
+    #[test]
+    fn finishing_order_follows_the_tricks() {
+        let mut state = endgame(config(3), &["7C", "8C 9H", "9D TD"], 0);
+        state.apply(&Action::Attack { card: card("7C") }).unwrap();
+        state.apply(&Action::Defend { pile_index: 0, card: card("8C") }).unwrap();
+        // P0 is out, but the trick only ends once both attackers pass
+        state.apply(&Action::PassAttack).unwrap();
+        assert!(state.finished.is_empty());
+        state.apply(&Action::PassAttack).unwrap();
+        assert_eq!(state.finished, vec![vec![pid(0)]]);
+
+        // P1 leads and goes out with their last card, P2 takes
+        state.apply(&Action::Attack { card: card("9H") }).unwrap();
+        state.apply(&Action::Take).unwrap();
+        let outcome = state.outcome().unwrap();
+        assert_eq!(outcome.placements(), [vec![pid(0)], vec![pid(1)]]);
+        assert_eq!(outcome.durak(), Some(pid(2)));
+    }
+
+    #[test]
+    fn throw_in_rule_selects_attackers() {
+        for n in 3..=6 {
+            let hands = vec!["6C 7C"; n];
+            let mut state = endgame(config(n), &hands, 1);
+            let others: Vec<PlayerId> = (1..=n).map(|i| pid(i % n)).filter(|&p| p != pid(2)).collect();
+            assert_eq!(state.attackers, others, "{n} players");
+
+            state.config.throw_in = ThrowIn::Neighbours;
+            let neighbours: Vec<PlayerId> = if n == 3 { vec![pid(1), pid(0)] } else { vec![pid(1), pid(3)] };
+            assert_eq!(state.build_attackers(pid(1)), neighbours, "{n} players");
+
+            state.config.throw_in = ThrowIn::MainAttacker;
+            assert_eq!(state.build_attackers(pid(1)), vec![pid(1)], "{n} players");
+        }
+    }
+
+    #[test]
+    fn seats_of_players_who_are_out_are_skipped() {
+        let mut state = endgame(config(5), &["6C", "7C", "8C", "", ""], 0);
+        assert_eq!(state.prev_active_player(pid(0)), pid(2));
+        assert_eq!(state.next_active_player(pid(2)), pid(0));
+
+        // Neighbours of the defender are the closest players still holding cards
+        state.config.throw_in = ThrowIn::Neighbours;
+        state.defender = pid(0);
+        assert_eq!(state.build_attackers(pid(1)), vec![pid(1), pid(2)]);
+    }
+
+    #[test]
+    fn reflect_passes_to_the_next_player_holding_cards() {
+        let config = GameConfig { reflecting: true, ..config(4) };
+        let mut state = endgame(config, &["6C 6S", "6D 7D", "", "8C 9C"], 0);
+        state.apply(&Action::Attack { card: card("6C") }).unwrap();
+        state.apply(&Action::Reflect { card: card("6D"), to: None }).unwrap();
+        assert_eq!(state.defender, pid(3));
+        assert_eq!(state.attackers, vec![pid(1), pid(0)]);
+    }
+
+    fn reflects(state: &GameState) -> Vec<Action> {
+        state.legal_actions().into_iter().filter(|a| matches!(a, Action::Reflect { .. })).collect()
+    }
+
+    #[test]
+    fn reflect_to_either_neighbour() {
+        let config = GameConfig { reflecting: true, reflect_direction: ReflectDirection::Either, ..config(4) };
+        let mut state = endgame(config, &["6C 8S 9S", "6D 7D", "8C 9C", "TC JC"], 0);
+        state.trump = Suit::Hearts;
+        state.apply(&Action::Attack { card: card("6C") }).unwrap();
+        let six = cards("6D")[0];
+        assert_eq!(
+            reflects(&state),
+            vec![Action::Reflect { card: six, to: Some(pid(2)) }, Action::Reflect { card: six, to: Some(pid(0)) }]
+        );
+        let err = state.clone().apply(&Action::Reflect { card: six, to: Some(pid(3)) }).unwrap_err();
+        assert_eq!(err, EngineError::ReflectNotAllowed { reason: ReflectError::DirectionNotAllowed });
+
+        state.apply(&Action::Reflect { card: six, to: Some(pid(0)) }).unwrap();
+        assert_eq!(state.defender, pid(0));
+    }
+
+    #[test]
+    fn reflects_per_trick_are_limited() {
+        let config = GameConfig { reflecting: true, max_reflects_per_trick: Some(1), ..config(3) };
+        let mut state = endgame(config, &["6C 8S 9S", "6D 7D", "6S 9C TD"], 0);
+        state.trump = Suit::Hearts;
+        state.apply(&Action::Attack { card: card("6C") }).unwrap();
+        state.apply(&Action::Reflect { card: card("6D"), to: None }).unwrap();
+        assert_eq!(state.defender, pid(2));
+
+        assert!(reflects(&state).is_empty());
+        let err = state.apply(&Action::Reflect { card: card("6S"), to: None }).unwrap_err();
+        assert_eq!(err, EngineError::ReflectNotAllowed { reason: ReflectError::LimitReached });
+    }
+
+    #[test]
+    fn reflect_back_to_the_original_attacker_can_be_forbidden() {
+        let config = GameConfig { reflecting: true, ..config(2) };
+        let mut state = endgame(config.clone(), &["6C 8S 9S", "6D 7D"], 0);
+        state.trump = Suit::Hearts;
+        state.apply(&Action::Attack { card: card("6C") }).unwrap();
+        assert_eq!(reflects(&state).len(), 1);
+
+        state.config = GameConfig { no_reflect_to_original_attacker: true, ..config };
+        assert!(reflects(&state).is_empty());
+        let err = state.apply(&Action::Reflect { card: card("6D"), to: None }).unwrap_err();
+        assert_eq!(err, EngineError::ReflectNotAllowed { reason: ReflectError::OriginalAttacker });
+    }
+
+    #[test]
+    fn hands_are_refilled_in_seat_order_from_the_main_attacker() {
+        let mut state = endgame(config(4), &["6C", "7C", "8C", "9C"], 2);
+        state.stock = cards("AS KS QS JS TS 9S");
+        state.refill_hands();
+        // Attackers draw first (P2, P0, P1), the defender P3 last
+        assert_eq!(state.hands[2], cards("8C 9S TS JS QS KS"));
+        assert_eq!(state.hands[0], cards("6C AS"));
+        assert_eq!(state.hands[1], cards("7C"));
+        assert_eq!(state.hands[3], cards("9C"));
+    }
+
+    #[test]
+    fn multi_card_plays_offer_the_lowest_cards_per_size() {
+        let config = GameConfig { multi_card_attacks: true, ..config(2) };
+        let mut state = endgame(config, &["6C 6H 6D 9S", "7C 8C"], 0);
+        state.trump = Suit::Hearts;
+        let plays = |state: &GameState| -> Vec<Vec<Card>> {
+            state
+                .legal_actions()
+                .into_iter()
+                .filter_map(|a| match a {
+                    Action::AttackMany { cards } => Some(cards),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        // The defender holds two cards, so two sixes may be played: the non-trumps are offered
+        assert_eq!(plays(&state), vec![cards("6C 6D")]);
+
+        // With room for three piles, the triple is offered as well
+        state.hands[1] = cards("7C 8C 9C");
+        assert_eq!(plays(&state), vec![cards("6C 6D"), cards("6C 6D 6H")]);
+
+        // Other sets are still allowed
+        state.apply(&Action::AttackMany { cards: cards("6H 6D") }).unwrap();
+        assert_eq!(state.hands[0], cards("6C 9S"));
+    }
+
+    #[test]
+    fn multi_card_plays_are_offered_for_unknown_hands() {
+        let config = GameConfig { multi_card_attacks: true, ..config(2) };
+        let mut state = endgame(config, &["6C 6D 6H", "7C 8C"], 0);
+        state.hands[0] = vec![Card::Unknown; 3];
+
+        let actions = state.legal_actions();
+        assert!(actions.contains(&Action::AttackMany { cards: vec![card("6C"), card("6D")] }));
+        // Not more cards than the defender can take on
+        assert!(!actions.iter().any(|a| matches!(a, Action::AttackMany { cards } if cards.len() > 2)));
+
+        state.hands[1] = cards("7C 8C 9C");
+        let sizes: HashSet<usize> = state
+            .legal_actions()
+            .into_iter()
+            .filter_map(|a| match a {
+                Action::AttackMany { cards } => Some(cards.len()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(sizes, HashSet::from([2, 3]));
+        // Only cards that might be in the hand
+        assert!(!actions.iter().any(|a| matches!(a, Action::AttackMany { cards } if cards.contains(&card("7C")))));
+    }
+
+    #[test]
+    fn game_ends_when_nobody_may_go_out() {
+        let config = GameConfig { end_rules: vec![EndRule::NoFinishWithTrump], ..config(2) };
+        let mut state = endgame(config.clone(), &["6H", "7H"], 0);
+        state.trump = Suit::Hearts;
+        assert!(state.is_terminal());
+        assert!(state.legal_actions().is_empty());
+        assert_eq!(state.outcome(), Some(Outcome::Draw { placements: vec![] }));
+
+        // Trumps change hands until a defense leaves each player with a single trump
+        let mut state = endgame(config, &["6H 8H", "7H"], 0);
+        state.trump = Suit::Hearts;
+        for seed in 0..1000 {
+            let Some(action) = pick_random_action(&state, seed) else {
+                break;
+            };
+            state.apply(&action).unwrap();
+        }
+        assert_eq!(state.outcome(), Some(Outcome::Draw { placements: vec![] }));
+    }
+
+    #[test]
+    fn forbidden_last_card_passes_the_attack_on() {
+        let config = GameConfig { end_rules: vec![EndRule::NoFinishWithTrump], ..config(3) };
+        let mut state = endgame(config, &["6H", "7C 8C", "9C"], 0);
+        state.trump = Suit::Hearts;
+        assert_eq!(state.legal_actions(), vec![Action::PassAttack]);
+        assert_eq!(state.apply(&Action::Attack { card: card("6H") }), Err(EngineError::ForbiddenFinish { card: card("6H") }));
+
+        state.apply(&Action::PassAttack).unwrap();
+        // P2 may open with their last card, which is not a trump
+        assert_eq!(state.actor_to_move(), pid(2));
+        assert!(state.legal_actions().contains(&Action::Attack { card: cards("9C")[0] }));
+    }
+
+    #[test]
+    fn jokers_count_as_trumps_for_the_last_card() {
+        let custom_deck = DeckSpec { jokers: 2, ..DeckSpec::standard(36).unwrap() };
+        let config = GameConfig { custom_deck: Some(custom_deck), end_rules: vec![EndRule::NoFinishWithTrump], ..config(3) };
+        let mut state = endgame(config, &["RJ", "7C 8C", "9C"], 0);
+        state.trump = Suit::Hearts;
+        assert_eq!(state.legal_actions(), vec![Action::PassAttack]);
+        assert_eq!(state.apply(&Action::Attack { card: card("RJ") }), Err(EngineError::ForbiddenFinish { card: card("RJ") }));
+    }
+
+    #[test]
+    fn epaulettes_are_reported_for_the_durak() {
+        let config = GameConfig { end_rules: vec![EndRule::Epaulettes { rank: Rank::Six }], ..config(2) };
+        let mut state = endgame(config, &["7C", "8D 6C 6S"], 0);
+        state.trump = Suit::Hearts;
+        state.apply(&Action::Attack { card: card("7C") }).unwrap();
+        state.apply(&Action::Take).unwrap();
+        let outcome = state.outcome().unwrap();
+        assert_eq!(outcome.durak(), Some(pid(1)));
+        assert_eq!(outcome.specials(), [SpecialOutcome::Epaulettes { player: pid(1), rank: Rank::Six }]);
+    }
+
+    #[test]
+    fn multi_card_play_rejects_more_copies_than_the_decks_hold() {
+        let config = GameConfig { multi_card_attacks: true, ..config(2) };
+        let mut state = endgame(config, &["6H 6C", "7C 8C"], 0);
+        state.hands[0][1] = Card::Unknown;
+        let twice = Action::AttackMany { cards: vec![card("6H"), card("6H")] };
+        assert_eq!(state.apply(&twice), Err(EngineError::InvalidMultiCard { reason: MultiCardError::TooManyCopies }));
+        assert!(state.table.is_empty());
+
+        state.config.decks = 2;
+        state.apply(&twice).unwrap();
+        assert_eq!(state.table.len(), 2);
+    }
+
+    #[test]
+    fn forbidden_finish_reports_the_forbidden_card() {
+        let config = GameConfig { end_rules: vec![EndRule::NoFinishWithTrump], multi_card_attacks: true, ..config(2) };
+        let mut state = endgame(config, &["6H 6C", "7C 8C"], 0);
+        state.trump = Suit::Hearts;
+        let play = Action::AttackMany { cards: vec![card("6H"), card("6C")] };
+        assert!(!state.legal_actions().contains(&play));
+        assert_eq!(state.apply(&play), Err(EngineError::ForbiddenFinish { card: card("6H") }));
+    }
+
+    #[test]
+    fn setup_errors_have_typed_reasons() {
+        let config = GameConfig { num_players: 7, ..GameConfig::default() };
+        assert_eq!(
+            config.validate(),
+            Err(EngineError::InvalidSetup { reason: SetupError::CannotDeal { hand_size: 6, num_players: 7, cards: 36 } })
+        );
+        let config = GameConfig { custom_deck: Some(DeckSpec { ranks: vec![], jokers: 2 }), ..GameConfig::default() };
+        assert_eq!(config.validate(), Err(EngineError::InvalidSetup { reason: SetupError::NoRanks }));
+    }
+
+    #[test]
+    fn defender_going_out_with_the_last_attacker_is_a_draw() {
+        let mut state = endgame(config(2), &["7C", "8C"], 0);
+        state.apply(&Action::Attack { card: card("7C") }).unwrap();
+        // The defender can still go out, so the game is not over yet
+        assert!(!state.is_terminal());
+        state.apply(&Action::Defend { pile_index: 0, card: card("8C") }).unwrap();
+
+        assert_eq!(state.outcome(), Some(Outcome::Draw { placements: vec![vec![pid(0), pid(1)]] }));
+        assert_eq!(state.durak(), None);
+        assert_eq!(MCTSObjective::NotDurak.reward(&state, pid(0)), 0.0);
+        assert_eq!(MCTSObjective::Placement.reward(&state, pid(0)), 0.5);
+    }
+
+    #[test]
+    fn first_trick_is_capped_at_its_limit() {
+        let config = GameConfig { first_trick_limit: Some(2), ..config(2) };
+        let mut state = endgame(config, &["6C 6D 6S 7C", "AC AD AS KC TH"], 0);
+        state.trump = Suit::Hearts;
+        state.tricks_played = 0;
+        state.apply(&Action::Attack { card: card("6C") }).unwrap();
+        state.apply(&Action::Defend { pile_index: 0, card: card("AC") }).unwrap();
+        state.apply(&Action::Attack { card: card("6D") }).unwrap();
+        state.apply(&Action::Defend { pile_index: 1, card: card("AD") }).unwrap();
+
+        assert!(!state.legal_actions().iter().any(|a| matches!(a, Action::Attack { .. })));
+        let mut first = state.clone();
+        assert_eq!(first.apply(&Action::Attack { card: card("6S") }), Err(EngineError::PileLimit { limit: 2 }));
+
+        // Later tricks are only limited by the defender's hand
+        state.tricks_played = 1;
+        state.apply(&Action::Attack { card: card("6S") }).unwrap();
+        assert_eq!(state.table.len(), 3);
+    }
+
+    #[test]
+    fn later_tricks_are_capped_by_max_piles_and_the_defender_hand() {
+        let config = GameConfig { max_piles: Some(3), multi_card_attacks: true, ..config(2) };
+        let mut state = endgame(config.clone(), &["6C 6D 6S 6H", "AC AD AS KC TH"], 0);
+        state.trump = Suit::Hearts;
+        let largest = state
+            .legal_actions()
+            .into_iter()
+            .filter_map(|a| match a {
+                Action::AttackMany { cards } => Some(cards.len()),
+                _ => None,
+            })
+            .max();
+        assert_eq!(largest, Some(3));
+        let err = state.clone().apply(&Action::AttackMany { cards: cards("6C 6D 6S 6H") }).unwrap_err();
+        assert_eq!(err, EngineError::PileLimit { limit: 3 });
+
+        // A defender holding fewer cards than the limit caps the trick
+        let mut state = endgame(config, &["6C 6D 6S 6H", "AC"], 0);
+        state.trump = Suit::Hearts;
+        assert!(!state.legal_actions().iter().any(|a| matches!(a, Action::AttackMany { .. })));
+        let err = state.apply(&Action::AttackMany { cards: cards("6C 6D") }).unwrap_err();
+        assert_eq!(err, EngineError::DefenderAtCapacity { defender: pid(1) });
+    }
+
+    #[test]
+    fn hands_are_dealt_and_refilled_to_the_hand_size() {
+        let config = GameConfig { hand_size: 4, ..config(3) };
+        let state = GameState::new_computer_game(0, config.clone()).unwrap();
+        assert!(state.hands.iter().all(|h| h.len() == 4));
+        assert_eq!(state.stock.len(), 36 - 12);
+
+        let mut state = endgame(config, &["6C", "7C 8C", "9C"], 0);
+        state.stock = cards("AS KS QS JS TS 9S 8S 7S 6S");
+        state.refill_hands();
+        assert!(state.hands.iter().all(|h| h.len() == 4));
+        assert_eq!(state.stock.len(), 1);
+    }
+
+    #[test]
+    fn trump_exchange_needs_the_lowest_trump_and_cards_above_the_face_up_trump() {
+        let config = GameConfig { trump_exchange: true, ..config(2) };
+        let mut state = endgame(config, &["6H 9C", "7H TC"], 0);
+        state.trump = Suit::Hearts;
+        state.stock = vec![card("AH"), card("8S").as_private()];
+        assert!(state.legal_actions().contains(&Action::ExchangeTrump));
+
+        // Only the holder of the lowest trump
+        let mut other = state.clone();
+        other.hands.swap(0, 1);
+        assert!(!other.legal_actions().contains(&Action::ExchangeTrump));
+        assert_eq!(other.apply(&Action::ExchangeTrump), Err(EngineError::TrumpExchangeNotAllowed));
+
+        // Not once the face-up trump is the last card
+        let mut last = state.clone();
+        last.stock.pop();
+        assert!(!last.legal_actions().contains(&Action::ExchangeTrump));
+
+        state.apply(&Action::ExchangeTrump).unwrap();
+        assert_eq!(state.stock[0], card("6H"));
+        assert!(state.stock[0].is_public());
+        assert_eq!(state.trump_card, Some(card("6H")));
+        assert!(state.hands[0].contains(&card("AH")));
+        assert_eq!(state.actor_to_move(), pid(0));
+        // The exchange is done for this trump card
+        assert!(!state.legal_actions().contains(&Action::ExchangeTrump));
+    }
+
+    #[test]
+    fn opponent_trump_exchange_is_recorded_as_known() {
+        let config = GameConfig { trump_exchange: true, ..config(2) };
+        let hand = cards("7S 8S 9S TS JS QS");
+        let mut state = GameState::new_manual_game(card("AH"), hand, 0, 1, vec![], config).unwrap();
+        assert_eq!(state.actor_to_move(), pid(1));
+        // The opponent might hold the six of trumps
+        assert!(state.legal_actions().contains(&Action::ExchangeTrump));
+
+        state.apply(&Action::ExchangeTrump).unwrap();
+        assert!(state.hands[1].contains(&card("AH")));
+        assert_eq!(state.hands[1].iter().filter(|c| c.is_unknown()).count(), 5);
+        assert_eq!(state.stock[0], card("6H"));
+        let deduction = state.deduce(pid(0));
+        assert!(deduction.cards.iter().all(|c| !c.card.matches(&card("AH")) && !c.card.matches(&card("6H"))));
+    }
+
+    #[test]
+    fn declared_trumps_are_deduced() {
+        let hand = cards("7S 8S 9S TS JS QS");
+        let declared = vec![(pid(1), TrumpDeclaration::NoTrump), (pid(2), TrumpDeclaration::Lowest { rank: Rank::Nine })];
+        let state = GameState::new_manual_game(card("AH"), hand, 0, 0, declared, config(4)).unwrap();
+        assert_eq!(state.hands[2][0], card("9H"));
+
+        // P1 holds no trump, P2 none below the nine, and P3 may hold any of them
+        let deduction = state.deduce(pid(0));
+        for c in deduction.cards.iter().filter(|c| c.card.is_trump(Suit::Hearts)) {
+            assert!(!c.possible.contains(&Location::Hand { player: pid(1) }), "{}", c.card);
+            assert_eq!(c.possible.contains(&Location::Hand { player: pid(2) }), c.card.rank() > Rank::Nine, "{}", c.card);
+            assert!(c.possible.contains(&Location::Hand { player: pid(3) }), "{}", c.card);
+        }
+    }
+
+    #[test]
+    fn team_durak_when_only_one_team_holds_cards() {
+        let config = GameConfig { teams: vec![0, 1, 0, 1], ..config(4) };
+        let mut state = endgame(config, &["7C", "9D TD", "QS", "8D"], 0);
+        state.trump = Suit::Hearts;
+        assert_eq!(state.defender, pid(1));
+        // Nobody can beat: P1 and then P3 take, P0 and P2 go out
+        for _ in 0..20 {
+            let actions = state.legal_actions();
+            let Some(action) = [Action::Take, Action::Throw { card: None }, Action::PassAttack]
+                .into_iter()
+                .find(|a| actions.contains(a))
+                .or_else(|| actions.first().cloned())
+            else {
+                break;
+            };
+            state.apply(&action).unwrap();
+        }
+
+        assert!(state.is_terminal());
+        let placements = vec![vec![pid(0)], vec![pid(2)]];
+        assert_eq!(
+            state.outcome(),
+            Some(Outcome::TeamDurak { team: 1, players: vec![pid(1), pid(3)], placements, specials: vec![] })
+        );
+        assert_eq!(state.durak(), None);
+        assert_eq!(state.losing_team(), Some(1));
+
+        let reward = |objective: MCTSObjective, i| objective.reward(&state, pid(i));
+        assert_eq!([0, 1, 2, 3].map(|i| reward(MCTSObjective::NotDurak, i)), [1.0, 0.0, 1.0, 0.0]);
+        // Teammates share the average placement of their team
+        let outcome = state.outcome().unwrap();
+        let team = (outcome.placement_score(pid(0), 4) + outcome.placement_score(pid(2), 4)) / 2.0;
+        assert_eq!(reward(MCTSObjective::Placement, 0), team);
+        assert_eq!(reward(MCTSObjective::Placement, 2), team);
+        assert_eq!(reward(MCTSObjective::Placement, 1), outcome.placement_score(pid(1), 4));
+        assert_eq!(reward(MCTSObjective::Placement, 3), reward(MCTSObjective::Placement, 1));
+        assert!(reward(MCTSObjective::Placement, 1) < team);
+    }
+
+    #[test]
+    fn player_ids_serialize_as_seat_names() {
+        assert_eq!(serde_json::to_string(&pid(3)).unwrap(), r#""P3""#);
+        assert_eq!(serde_json::from_str::<PlayerId>(r#""P3""#).unwrap(), pid(3));
+        // Seat numbers are accepted as well
+        assert_eq!(serde_json::from_str::<PlayerId>("3").unwrap(), pid(3));
+        assert!(serde_json::from_str::<PlayerId>(r#""3""#).is_err());
+        assert!(serde_json::from_str::<PlayerId>("256").is_err());
+        assert!(serde_json::from_str::<PlayerId>("-1").is_err());
+
+        let state = GameState::new_computer_game(0, config(3)).unwrap();
+        let json = serde_json::to_value(&state).unwrap();
+        assert_eq!(json["attacker"], serde_json::json!(state.attacker.to_string()));
+        assert_eq!(serde_json::from_value::<GameState>(json.clone()).unwrap(), state);
+
+        // States saved with numbered seats still load
+        let mut old = json;
+        old["attacker"] = serde_json::json!(state.attacker.index());
+        old["defender"] = serde_json::json!(state.defender.index());
+        assert_eq!(serde_json::from_value::<GameState>(old).unwrap(), state);
+    }
+
+    #[test]
+    fn seats_outside_the_table_are_rejected() {
+        assert_eq!(PlayerId::new(2, 3), Ok(pid(2)));
+        assert_eq!(PlayerId::new(3, 3), Err(EngineError::InvalidSeat { seat: 3, num_players: 3 }));
+        assert_eq!(pid(5).validate(4), Err(EngineError::InvalidSeat { seat: 5, num_players: 4 }));
+
+        let mut state = GameState::new_computer_game(0, config(3)).unwrap();
+        state.defender = pid(3);
+        assert!(state.validate().is_err());
+    }
+
+    fn joker_config(jokers: usize) -> GameConfig {
+        GameConfig { custom_deck: Some(DeckSpec { jokers, ..DeckSpec::standard(36).unwrap() }), ..config(2) }
+    }
+
+    #[test]
+    fn jokers_beat_everything_and_cannot_be_beaten() {
+        for c in ["6C", "AH", "AS"] {
+            assert!(card("RJ").beats(&card(c), Suit::Hearts));
+            assert!(!card(c).beats(&card("BJ"), Suit::Hearts));
+        }
+        assert!(!card("RJ").beats(&card("BJ"), Suit::Hearts));
+        assert!(!card("RJ").is_trump(Suit::Hearts));
+    }
+
+    #[test]
+    fn jokers_have_a_short_notation() {
+        assert_eq!(card("RJ"), Card::public(Suit::Hearts, Rank::Joker));
+        assert_eq!(card("bj"), Card::public(Suit::Spades, Rank::Joker));
+        assert_eq!(card("RJ").to_string(), "RJ");
+        assert_eq!(card("BJ").to_string(), "BJ");
+        assert_eq!(card("BJ").to_string().parse::<Card>().unwrap(), card("BJ"));
+        assert!("XJ".parse::<Card>().is_err());
+    }
+
+    #[test]
+    fn jokers_join_any_trick_only_when_wild() {
+        let mut state = endgame(joker_config(2), &["7C RJ 9S", "8C 9D TD"], 0);
+        state.trump = Suit::Hearts;
+        state.apply(&Action::Attack { card: card("7C") }).unwrap();
+        state.apply(&Action::Defend { pile_index: 0, card: card("8C") }).unwrap();
+        let joker = cards("RJ")[0];
+        assert!(!state.legal_actions().contains(&Action::Attack { card: joker }));
+        assert!(matches!(state.clone().apply(&Action::Attack { card: joker }), Err(EngineError::RankNotOnTable { .. })));
+
+        state.config.joker_matching = JokerMatching::Any;
+        assert!(state.legal_actions().contains(&Action::Attack { card: joker }));
+        state.apply(&Action::Attack { card: joker }).unwrap();
+        // Nothing beats it
+        assert_eq!(state.legal_actions(), vec![Action::Take]);
+    }
+
+    #[test]
+    fn several_jokers_of_a_colour_can_be_played_together() {
+        let config = GameConfig { multi_card_attacks: true, ..joker_config(4) };
+        let mut state = endgame(config, &["RJ RJ BJ BJ", "6C 7C 8C 9C"], 0);
+        state.trump = Suit::Hearts;
+        let play = Action::AttackMany { cards: cards("RJ RJ BJ BJ") };
+        assert!(state.legal_actions().contains(&play));
+
+        let err = state.clone().apply(&Action::AttackMany { cards: cards("RJ RJ RJ") }).unwrap_err();
+        assert_eq!(err, EngineError::InvalidMultiCard { reason: MultiCardError::TooManyCopies });
+        state.apply(&play).unwrap();
+        assert_eq!(state.table.len(), 4);
+    }
+
+    #[test]
+    fn errors_name_the_rejected_action() {
+        let mut state = endgame(config(2), &["7C", "8C"], 0);
+        let err = state.apply(&Action::Take).unwrap_err();
+        assert_eq!(err, EngineError::WrongPhase { phase: Phase::Attacking, action: ActionKind::Take });
+        assert_eq!(err.to_string(), "take is not allowed in the Attacking phase");
+
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(json, serde_json::json!({ "code": "wrong_phase", "phase": "Attacking", "action": "take" }));
+        let json = serde_json::to_value(EngineError::InvalidState { reason: StateError::NotPlayed { card: card("6H") } }).unwrap();
+        assert_eq!(json["code"], "invalid_state");
+        assert_eq!(json["reason"]["type"], "not_played");
+    }
+
+    /// Three players: P0 attacked P1 with 7C, which P1 beat with 8C.
+    fn trick_in_progress(turn_model: TurnModel) -> GameState {
+        let config = GameConfig { turn_model, ..config(3) };
+        let mut state = endgame(config, &["7C 7D 9S", "8C 8D TC", "7S 8S 7H"], 0);
+        state.trump = Suit::Hearts;
+        state.apply(&Action::Attack { card: card("7C") }).unwrap();
+        state.apply(&Action::Defend { pile_index: 0, card: card("8C") }).unwrap();
+        state
+    }
+
+    #[test]
+    fn players_act_only_in_turn() {
+        let mut state = trick_in_progress(TurnModel::Sequential);
+        assert_eq!(state.eligible_players(), vec![pid(0), pid(2)]);
+        // The defender cannot add cards to their own trick
+        let err = state.clone().apply_as(pid(1), &Action::Attack { card: card("8D") }).unwrap_err();
+        assert_eq!(err, EngineError::NotYourTurn { player: pid(1), to_move: pid(0) });
+        // Co-attackers may only add cards, not pass for the player to move
+        let err = state.clone().apply_as(pid(2), &Action::PassAttack).unwrap_err();
+        assert_eq!(err, EngineError::NotYourTurn { player: pid(2), to_move: pid(0) });
+
+        state.apply_as(pid(2), &Action::Attack { card: card("8S") }).unwrap();
+        assert_eq!(state.last_played_attacker, pid(2));
+        assert_eq!(state.eligible_players(), vec![pid(1)]);
+        let err = state.apply_as(pid(0), &Action::Attack { card: card("7D") }).unwrap_err();
+        assert_eq!(err, EngineError::NotYourTurn { player: pid(0), to_move: pid(1) });
+    }
+
+    #[test]
+    fn co_attackers_join_the_throw_in() {
+        let mut state = trick_in_progress(TurnModel::Sequential);
+        state.apply(&Action::Attack { card: card("7D") }).unwrap();
+        state.apply(&Action::Take).unwrap();
+        assert_eq!(state.eligible_players(), vec![pid(0), pid(2)]);
+
+        state.apply_as(pid(2), &Action::Throw { card: Some(card("7S")) }).unwrap();
+        assert_eq!(state.table.len(), 3);
+        // Everyone gets another chance after the card thrown out of turn
+        state.apply(&Action::Throw { card: None }).unwrap();
+        state.apply(&Action::Throw { card: None }).unwrap();
+        assert!(state.table.is_empty());
+        assert_eq!(state.hands[1].len(), 6);
+    }
+
+    #[test]
+    fn races_go_to_the_first_eligible_player_whose_action_applies() {
+        let state = trick_in_progress(TurnModel::Sequential);
+        let co_attacker = (pid(2), Action::Attack { card: card("7S") });
+        // The player to move comes first in `eligible_players`
+        assert_eq!(state.resolve_race(&[co_attacker.clone(), (pid(0), Action::Attack { card: card("7D") })]), Some(1));
+        // 9S is not on the table, so the co-attacker wins
+        assert_eq!(state.resolve_race(&[co_attacker.clone(), (pid(0), Action::Attack { card: card("9S") })]), Some(0));
+        // Submission order decides between two actions of the same player
+        let both = [(pid(2), Action::Attack { card: card("8S") }), co_attacker];
+        assert_eq!(state.resolve_race(&both), Some(0));
+        assert_eq!(state.resolve_race(&[(pid(1), Action::Take)]), None);
+    }
+
+    #[test]
+    fn new_cards_clear_done_votes() {
+        let mut state = trick_in_progress(TurnModel::Simultaneous);
+        state.apply_as(pid(2), &Action::PassAttack).unwrap();
+        assert_eq!(state.done_attackers, vec![pid(2)]);
+        assert_eq!(state.eligible_players(), vec![pid(0)]);
+
+        state.apply(&Action::Attack { card: card("7D") }).unwrap();
+        assert!(state.done_attackers.is_empty());
+        // Attackers may keep adding cards while the defender defends
+        assert_eq!(state.eligible_players(), vec![pid(1), pid(0), pid(2)]);
+    }
+
+    #[test]
+    fn trick_ends_once_every_attacker_is_done() {
+        let mut state = trick_in_progress(TurnModel::Simultaneous);
+        state.apply_as(pid(2), &Action::PassAttack).unwrap();
+        assert_eq!(state.table.len(), 1);
+        state.apply(&Action::PassAttack).unwrap();
+        assert!(state.table.is_empty());
+        assert_eq!(state.discard.iter().filter(|c| c.matches(&card("8C"))).count(), 1);
+        // P1 defended, so they attack next
+        assert_eq!(state.attacker, pid(1));
+    }
+
+    #[test]
+    fn attackers_who_went_out_are_not_waited_on() {
+        let config = GameConfig { turn_model: TurnModel::Simultaneous, ..config(3) };
+        let mut state = endgame(config, &["7C", "8C 8D TC", "7S 8S 7H"], 0);
+        state.trump = Suit::Hearts;
+        state.apply(&Action::Attack { card: card("7C") }).unwrap();
+        state.apply(&Action::Defend { pile_index: 0, card: card("8C") }).unwrap();
+        assert_eq!(state.hand_size(pid(0)), 0);
+
+        state.apply_as(pid(2), &Action::PassAttack).unwrap();
+        assert!(state.table.is_empty());
+        assert_eq!(state.finished, vec![vec![pid(0)]]);
+    }
+
+    #[test]
+    fn vote_out_of_turn_records_who_went_out() {
+        let config = GameConfig { turn_model: TurnModel::Simultaneous, ..config(3) };
+        let mut state = endgame(config, &["7C 7D", "8C 9S 9D", "7S 8D"], 0);
+        state.trump = Suit::Hearts;
+        state.apply(&Action::Attack { card: card("7C") }).unwrap();
+        state.apply(&Action::Take).unwrap();
+        // P0 throws their last card, the turn stays with them
+        state.apply(&Action::Throw { card: Some(card("7D")) }).unwrap();
+        assert_eq!(state.actor_to_move(), pid(0));
+
+        // P2's vote ends the trick without P0 acting again
+        state.apply_as(pid(2), &Action::Throw { card: None }).unwrap();
+        assert!(state.table.is_empty());
+        assert_eq!(state.hand_size(pid(1)), 5);
+        assert_eq!(state.finished, vec![vec![pid(0)]]);
+    }
+}
@@ -0,0 +1,375 @@
+//! Entering a real game in progress, for the manual-mode helper.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{unseen_cards, Card, EngineError, GameConfig, GameState, Phase, Pile, PlayerId, SetupError};
+
+/// Description of a game in progress from your seat, for starting the helper mid-game.
+///
+/// Opponent hands and the stock are filled with `Card::Unknown` placeholders. The face-up
+/// trump card is the bottom of the stock while the stock has cards. Discards that aren't
+/// entered are kept as unknown cards, which deduction treats as one more hidden place.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
+pub struct ManualPositionBuilder {
+    /// Your seat
+    #[serde(default)]
+    pub seat: PlayerId,
+    /// The face-up trump card (it sets the trump suit even once drawn)
+    pub trump_card: Card,
+    /// Your current hand
+    #[serde(default)]
+    pub hand: Vec<Card>,
+    /// Number of cards in each opponent's hand
+    #[serde(default)]
+    pub hand_sizes: Vec<(PlayerId, usize)>,
+    /// Number of cards left in the stock, including the face-up trump card
+    #[serde(default)]
+    pub stock_size: usize,
+    /// Cards on the table in the current trick
+    #[serde(default)]
+    pub table: Vec<Pile>,
+    /// Discarded cards you remember
+    #[serde(default)]
+    pub discard: Vec<Card>,
+    /// Main attacker of the current trick
+    pub attacker: PlayerId,
+    /// Defender of the current trick (by default the next opponent after the attacker)
+    #[serde(default)]
+    pub defender: Option<PlayerId>,
+    /// The defender has decided to take, and attackers may throw in more cards
+    #[serde(default)]
+    pub taking: bool,
+    /// The current trick is the first of the game
+    #[serde(default)]
+    pub first_trick: bool,
+    /// Players who have already gone out, in finishing order
+    #[serde(default)]
+    pub finished: Vec<Vec<PlayerId>>,
+}
+
+impl ManualPositionBuilder {
+    pub fn new(trump_card: Card, attacker: PlayerId) -> Self {
+        Self {
+            seat: PlayerId::default(),
+            trump_card,
+            hand: vec![],
+            hand_sizes: vec![],
+            stock_size: 0,
+            table: vec![],
+            discard: vec![],
+            attacker,
+            defender: None,
+            taking: false,
+            first_trick: false,
+            finished: vec![],
+        }
+    }
+
+    pub fn seat(mut self, seat: PlayerId) -> Self {
+        self.seat = seat;
+        self
+    }
+
+    pub fn hand(mut self, hand: Vec<Card>) -> Self {
+        self.hand = hand;
+        self
+    }
+
+    pub fn hand_size(mut self, player: PlayerId, size: usize) -> Self {
+        self.hand_sizes.retain(|(p, _)| *p != player);
+        self.hand_sizes.push((player, size));
+        self
+    }
+
+    pub fn stock_size(mut self, size: usize) -> Self {
+        self.stock_size = size;
+        self
+    }
+
+    pub fn table(mut self, table: Vec<Pile>) -> Self {
+        self.table = table;
+        self
+    }
+
+    pub fn discard(mut self, discard: Vec<Card>) -> Self {
+        self.discard = discard;
+        self
+    }
+
+    pub fn defender(mut self, defender: PlayerId) -> Self {
+        self.defender = Some(defender);
+        self
+    }
+
+    pub fn taking(mut self, taking: bool) -> Self {
+        self.taking = taking;
+        self
+    }
+
+    pub fn first_trick(mut self, first_trick: bool) -> Self {
+        self.first_trick = first_trick;
+        self
+    }
+
+    pub fn finished(mut self, finished: Vec<Vec<PlayerId>>) -> Self {
+        self.finished = finished;
+        self
+    }
+
+    /// Build the game state, checking that the position is consistent with the deck.
+    pub fn build(&self, config: GameConfig) -> Result<GameState, EngineError> {
+        config.validate()?;
+        let n = config.num_players;
+        let spec = config.deck_spec()?;
+        let total = config.total_cards()?;
+
+        if self.trump_card.is_unknown() || self.trump_card.is_joker() {
+            return Err(EngineError::setup(SetupError::TrumpCardNotSuited));
+        }
+        let trump = self.trump_card.suit();
+
+        // Hand sizes per seat
+        let me = self.seat.validate(n)?;
+        let mut sizes = vec![None; n];
+        sizes[me.index()] = Some(self.hand.len());
+        for &(pid, size) in &self.hand_sizes {
+            pid.validate(n)?;
+            if pid == me {
+                return Err(EngineError::setup(SetupError::OwnHandSizeGiven));
+            }
+            if sizes[pid.index()].replace(size).is_some() {
+                return Err(EngineError::setup(SetupError::HandSizeGivenTwice { player: pid }));
+            }
+        }
+        let sizes: Vec<usize> = sizes
+            .into_iter()
+            .enumerate()
+            .map(|(i, size)| {
+                size.ok_or_else(|| EngineError::setup(SetupError::MissingHandSize { player: PlayerId::from_index(i) }))
+            })
+            .collect::<Result<_, _>>()?;
+
+        // Known cards must belong to the deck, without more copies than it has
+        let table_cards: Vec<Card> =
+            self.table.iter().flat_map(|p| std::iter::once(p.attack).chain(p.defense)).collect();
+        let mut known: Vec<Card> = self.hand.iter().chain(&table_cards).chain(&self.discard).copied().collect();
+        if self.stock_size > 0 {
+            known.push(self.trump_card);
+        }
+        if let Some(card) = known.iter().find(|c| c.is_unknown() || !spec.contains(c)) {
+            return Err(EngineError::setup(SetupError::CardNotInDeck { card: *card }));
+        }
+        if unseen_cards(&config, &known)?.len() + known.len() != total {
+            return Err(EngineError::setup(SetupError::TooManyCopies));
+        }
+
+        let placed = sizes.iter().sum::<usize>() + self.stock_size + table_cards.len();
+        if placed + self.discard.len() > total {
+            return Err(EngineError::setup(SetupError::TooManyCards { entered: placed + self.discard.len(), cards: total }));
+        }
+
+        // Seats of the current trick
+        let attacker = self.attacker.validate(n)?;
+        if let Some(defender) = self.defender {
+            defender.validate(n)?;
+            if defender == attacker {
+                return Err(EngineError::setup(SetupError::AttackerDefends));
+            }
+        }
+        let mut out: Vec<PlayerId> = Vec::new();
+        for &pid in self.finished.iter().flatten() {
+            pid.validate(n)?;
+            if sizes[pid.index()] > 0 || self.stock_size > 0 || out.contains(&pid) {
+                return Err(EngineError::setup(SetupError::CannotHaveGoneOut { player: pid }));
+            }
+            out.push(pid);
+        }
+
+        // Table: every defense beats its attack, and open piles only while defending
+        for (i, pile) in self.table.iter().enumerate() {
+            if let Some(defense) = pile.defense
+                && !defense.beats(&pile.attack, trump)
+            {
+                return Err(EngineError::setup(SetupError::DefenseDoesNotBeat { pile: i, card: defense, attack: pile.attack }));
+            }
+        }
+        let open = self.table.iter().any(|p| p.defense.is_none());
+        let phase = match (self.taking, open) {
+            (true, _) if self.table.is_empty() => {
+                return Err(EngineError::setup(SetupError::NothingToTake));
+            }
+            (true, _) => Phase::Throwing,
+            (false, true) => Phase::Defending,
+            (false, false) => Phase::Attacking,
+        };
+
+        let hands: Vec<Vec<Card>> = sizes
+            .iter()
+            .enumerate()
+            .map(|(i, &size)| {
+                if i == me.index() {
+                    self.hand.iter().map(|c| if c.is_public() { *c } else { c.as_private() }).collect()
+                } else {
+                    vec![Card::Unknown; size]
+                }
+            })
+            .collect();
+
+        let mut stock = vec![Card::Unknown; self.stock_size];
+        if let Some(bottom) = stock.first_mut() {
+            *bottom = self.trump_card.as_public();
+        }
+
+        let mut discard: Vec<Card> = self.discard.iter().map(|c| c.as_public()).collect();
+        discard.resize(total - placed, Card::Unknown);
+
+        let table = self
+            .table
+            .iter()
+            .map(|p| Pile { attack: p.attack.as_public(), defense: p.defense.map(|d| d.as_public()) })
+            .collect();
+
+        let mut state = GameState {
+            trump,
+            trump_card: Some(self.trump_card.as_public()),
+            attacker,
+            defender: attacker,
+            phase,
+            attackers: vec![],
+            current_attacker_idx: 0,
+            last_played_attacker: attacker,
+            throw_start_idx: 0,
+            hands,
+            stock,
+            table,
+            discard,
+            reflected_trumps: vec![],
+            original_attacker: attacker,
+            reflects: 0,
+            tricks_played: if self.first_trick { 0 } else { 1 },
+            finished: self.finished.clone(),
+            done_attackers: vec![],
+            voids: vec![],
+            config,
+        };
+        state.defender = self.defender.unwrap_or_else(|| state.next_opponent(attacker));
+        if !state.is_player_active(attacker) || !state.is_player_active(state.defender) {
+            return Err(EngineError::setup(SetupError::PlayerOut));
+        }
+        if phase == Phase::Defending && state.undefended_pile_count() > state.hand_size(state.defender) {
+            return Err(EngineError::setup(SetupError::DefenderShortOfCards));
+        }
+        state.attackers = state.build_attackers(attacker);
+        state.record_finishers();
+        Ok(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{card, pid};
+
+    fn config() -> GameConfig {
+        GameConfig { num_players: 3, ..GameConfig::default() }
+    }
+
+    /// Three players after the first trick, from P0's seat: P1 attacks P2.
+    fn builder() -> ManualPositionBuilder {
+        ManualPositionBuilder::new(card("6H"), pid(1))
+            .hand(["6S", "7S", "8S", "9S", "TS", "JS"].map(card).to_vec())
+            .hand_size(pid(1), 6)
+            .hand_size(pid(2), 6)
+            .stock_size(12)
+            .discard(vec![card("6C"), card("7C")])
+    }
+
+    fn setup_error(result: Result<GameState, EngineError>) -> SetupError {
+        match result {
+            Err(EngineError::InvalidSetup { reason }) => reason,
+            other => panic!("expected a setup error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn builds_the_position_from_your_seat() {
+        let state = builder().build(config()).unwrap();
+        assert_eq!(state.phase, Phase::Attacking);
+        assert_eq!((state.attacker, state.defender), (pid(1), pid(2)));
+        assert!(state.hands[0].iter().all(|c| !c.is_unknown()));
+        assert!(state.hands[1].iter().chain(&state.hands[2]).all(|c| c.is_unknown()));
+        assert_eq!(state.stock.len(), 12);
+        assert_eq!(state.stock[0], card("6H").as_public());
+        // Discards that weren't entered are unknown
+        assert_eq!(state.discard.len(), 6);
+        assert_eq!(state.discard.iter().filter(|c| c.is_unknown()).count(), 4);
+        state.validate().unwrap();
+    }
+
+    #[test]
+    fn table_sets_the_phase() {
+        let open = vec![Pile { attack: card("7H"), defense: None }];
+        let state = builder().hand_size(pid(1), 5).table(open.clone()).build(config()).unwrap();
+        assert_eq!(state.phase, Phase::Defending);
+
+        let state = builder().hand_size(pid(1), 5).table(open).taking(true).build(config()).unwrap();
+        assert_eq!(state.phase, Phase::Throwing);
+
+        let beaten = vec![Pile { attack: card("7H"), defense: Some(card("6D")) }];
+        let err = builder().hand_size(pid(1), 5).hand_size(pid(2), 5).table(beaten).build(config());
+        assert_eq!(setup_error(err), SetupError::DefenseDoesNotBeat { pile: 0, card: card("6D"), attack: card("7H") });
+    }
+
+    #[test]
+    fn hand_sizes_are_checked() {
+        assert_eq!(setup_error(builder().hand_size(pid(0), 6).build(config())), SetupError::OwnHandSizeGiven);
+
+        let mut twice = builder();
+        twice.hand_sizes.push((pid(2), 5));
+        assert_eq!(setup_error(twice.build(config())), SetupError::HandSizeGivenTwice { player: pid(2) });
+
+        let mut missing = builder();
+        missing.hand_sizes.retain(|(p, _)| *p != pid(2));
+        assert_eq!(setup_error(missing.build(config())), SetupError::MissingHandSize { player: pid(2) });
+    }
+
+    #[test]
+    fn known_cards_must_fit_the_deck() {
+        let err = builder().discard(vec![card("2C")]).build(config());
+        assert_eq!(setup_error(err), SetupError::CardNotInDeck { card: card("2C") });
+
+        let err = builder().discard(vec![card("6S")]).build(config());
+        assert_eq!(setup_error(err), SetupError::TooManyCopies);
+
+        let err = builder().stock_size(20).build(config());
+        assert_eq!(setup_error(err), SetupError::TooManyCards { entered: 40, cards: 36 });
+
+        let err = ManualPositionBuilder::new(Card::Unknown, pid(1)).build(config());
+        assert_eq!(setup_error(err), SetupError::TrumpCardNotSuited);
+    }
+
+    #[test]
+    fn seats_of_the_trick_are_checked() {
+        assert_eq!(setup_error(builder().defender(pid(1)).build(config())), SetupError::AttackerDefends);
+        assert_eq!(setup_error(builder().taking(true).build(config())), SetupError::NothingToTake);
+
+        // Nobody goes out while the stock has cards
+        let err = builder().hand_size(pid(2), 0).finished(vec![vec![pid(2)]]).build(config());
+        assert_eq!(setup_error(err), SetupError::CannotHaveGoneOut { player: pid(2) });
+
+        let open = vec![Pile { attack: card("7H"), defense: None }, Pile { attack: card("7D"), defense: None }];
+        let err = builder().hand_size(pid(1), 4).hand_size(pid(2), 1).table(open).build(config());
+        assert_eq!(setup_error(err), SetupError::DefenderShortOfCards);
+    }
+
+    #[test]
+    fn players_who_went_out_are_skipped() {
+        let out = builder().stock_size(0).hand_size(pid(1), 0).finished(vec![vec![pid(1)]]);
+        assert_eq!(setup_error(out.clone().build(config())), SetupError::PlayerOut);
+
+        let state = ManualPositionBuilder { attacker: pid(2), ..out }.build(config()).unwrap();
+        assert_eq!(state.defender, pid(0));
+        assert_eq!(state.finished, vec![vec![pid(1)]]);
+    }
+}
@@ -0,0 +1,248 @@
+//! UCT search over determinizations with one tree for all of them, kept between searches.
+//!
+//! Each search deals the hidden cards several times and runs rollouts on every deal, growing a
+//! tree keyed by actions with the card visibility left out, so the same move in different deals
+//! shares a node. Rewards are kept for every player, and after a move is played its subtree
+//! becomes the new root, so later searches build on what was found before.
+
+use std::collections::HashMap;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{Action, Card, GameState, MCTSObjective, PlayerId, RolloutActionResult};
+
+/// Settings the statistics of a search tree depend on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchSettings {
+    pub objective: MCTSObjective,
+    /// Player whose knowledge is used to deal the hidden cards
+    pub perspective: PlayerId,
+    /// Maximum number of random moves per rollout
+    pub max_depth: u32,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Node {
+    visits: u32,
+    /// Summed rollout rewards per player
+    rewards: Vec<f64>,
+    children: HashMap<Action, Node>,
+}
+
+impl Node {
+    fn add(&mut self, rewards: &[f64]) {
+        self.visits += 1;
+        self.rewards.resize(rewards.len(), 0.0);
+        for (sum, r) in self.rewards.iter_mut().zip(rewards) {
+            *sum += r;
+        }
+    }
+
+    fn score(&self, player: PlayerId) -> f64 {
+        if self.visits == 0 {
+            return 0.0;
+        }
+        self.rewards.get(player.index()).copied().unwrap_or(0.0) / self.visits as f64
+    }
+}
+
+/// A search tree for the current state of a game (see the module docs).
+#[derive(Debug, Clone, Default)]
+pub struct SearchTree {
+    settings: Option<SearchSettings>,
+    root: Node,
+    determinizations: u32,
+}
+
+impl SearchTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Search `state` for the actor to move: `rollouts` rollouts on each of `determinizations`
+    /// deals of the cards hidden from `settings.perspective`, adding to the tree. A tree grown
+    /// with other settings is cleared first.
+    pub fn search(&mut self, state: &GameState, settings: SearchSettings, determinizations: u32, rollouts: u32, seed: u64) {
+        if self.settings != Some(settings) {
+            *self = Self { settings: Some(settings), ..Self::default() };
+        }
+        let mut rng = StdRng::seed_from_u64(seed);
+        for _ in 0..determinizations {
+            let dealt = state.determinize(settings.perspective, &mut rng);
+            for _ in 0..rollouts {
+                self.rollout(&dealt, settings, &mut rng);
+            }
+        }
+        self.determinizations += determinizations;
+    }
+
+    fn rollout(&mut self, state: &GameState, settings: SearchSettings, rng: &mut StdRng) {
+        let mut s = state.clone();
+        let mut path: Vec<Action> = vec![];
+        let mut node = &mut self.root;
+
+        // Selection among the moves legal in this deal, until one is expanded.
+        // Each node picks the move that is best for the player choosing it.
+        while !s.is_terminal() {
+            let actions: Vec<(Action, Action)> = s.legal_actions().into_iter().map(|a| (key(&a), a)).collect();
+            if actions.is_empty() {
+                break;
+            }
+            if let Some((k, action)) = actions.iter().find(|(k, _)| !node.children.contains_key(k)) {
+                let _ = s.apply(action);
+                node.children.insert(k.clone(), Node::default());
+                path.push(k.clone());
+                break;
+            }
+            let mover = s.actor_to_move();
+            let ln_n = (node.visits.max(1) as f64).ln();
+            let uct = |child: &Node| child.score(mover) + UCT_C * (ln_n / child.visits.max(1) as f64).sqrt();
+            let (k, action) = actions
+                .iter()
+                .max_by(|(a, _), (b, _)| uct(&node.children[a]).total_cmp(&uct(&node.children[b])))
+                .unwrap();
+            let _ = s.apply(action);
+            path.push(k.clone());
+            node = node.children.get_mut(k).unwrap();
+        }
+
+        // Simulation: random playout
+        let mut depth = 0;
+        while !s.is_terminal() && depth < settings.max_depth {
+            let acts = s.legal_actions();
+            if acts.is_empty() {
+                break;
+            }
+            let _ = s.apply(&acts[rng.gen_range(0..acts.len())]);
+            depth += 1;
+        }
+        let rewards: Vec<f64> =
+            (0..s.num_players()).map(|i| settings.objective.reward(&s, PlayerId::from_index(i))).collect();
+
+        // Backpropagation
+        let mut node = &mut self.root;
+        node.add(&rewards);
+        for k in &path {
+            node = node.children.get_mut(k).unwrap();
+            node.add(&rewards);
+        }
+    }
+
+    /// Move the root to the subtree of `action`, played by the actor to move.
+    /// The tree is cleared if the action was never searched.
+    pub fn advance(&mut self, action: &Action) {
+        match self.root.children.remove(&key(action)) {
+            Some(child) => self.root = child,
+            None => self.clear(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Number of deals searched since the tree was cleared.
+    pub fn determinizations(&self) -> u32 {
+        self.determinizations
+    }
+
+    /// Number of rollouts through the root.
+    pub fn total_visits(&self) -> u32 {
+        self.root.visits
+    }
+
+    /// Visits and score for `player` of a move from the root (none if it wasn't searched).
+    pub fn result(&self, action: &Action, player: PlayerId) -> RolloutActionResult {
+        let (visits, score) = self
+            .root
+            .children
+            .get(&key(action))
+            .map_or((0, 0.0), |n| (n.visits, n.score(player) as f32));
+        RolloutActionResult { action: action.clone(), visits, score }
+    }
+}
+
+/// Exploration constant of the UCT formula.
+const UCT_C: f64 = 1.41;
+
+/// The action with its cards public and in a fixed order, so the same move matches across deals.
+fn key(action: &Action) -> Action {
+    let cards = |cards: &[Card]| {
+        let mut cards: Vec<Card> = cards.iter().map(|c| c.as_public()).collect();
+        cards.sort_by_key(|c| (c.suit() as u8, c.rank()));
+        cards
+    };
+    match action {
+        Action::Attack { card } => Action::Attack { card: card.as_public() },
+        Action::AttackMany { cards: c } => Action::AttackMany { cards: cards(c) },
+        Action::Defend { pile_index, card } => Action::Defend { pile_index: *pile_index, card: card.as_public() },
+        Action::Throw { card } => Action::Throw { card: card.map(|c| c.as_public()) },
+        Action::ThrowMany { cards: c } => Action::ThrowMany { cards: cards(c) },
+        Action::Reflect { card, to } => Action::Reflect { card: card.as_public(), to: *to },
+        Action::ReflectTrump { card, to } => Action::ReflectTrump { card: card.as_public(), to: *to },
+        Action::PassAttack | Action::Take | Action::ExchangeTrump => action.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GameConfig;
+    use crate::test_util::{card, config, endgame, pid};
+
+    fn settings(perspective: PlayerId) -> SearchSettings {
+        SearchSettings { objective: MCTSObjective::NotDurak, perspective, max_depth: 100 }
+    }
+
+    #[test]
+    fn searches_add_up_until_the_settings_change() {
+        let state = GameState::new_computer_game(1, GameConfig::default()).unwrap();
+        let actor = state.actor_to_move();
+        let mut tree = SearchTree::new();
+        tree.search(&state, settings(actor), 2, 50, 0);
+        tree.search(&state, settings(actor), 2, 50, 1);
+        assert_eq!((tree.determinizations(), tree.total_visits()), (4, 200));
+        let visits: u32 = state.legal_actions().iter().map(|a| tree.result(a, actor).visits).sum();
+        assert_eq!(visits, 200);
+
+        tree.search(&state, settings(actor.next(state.num_players())), 1, 10, 2);
+        assert_eq!((tree.determinizations(), tree.total_visits()), (1, 10));
+    }
+
+    #[test]
+    fn played_moves_keep_their_subtree() {
+        let mut state = GameState::new_computer_game(1, GameConfig::default()).unwrap();
+        let actor = state.actor_to_move();
+        let mut tree = SearchTree::new();
+        tree.search(&state, settings(actor), 4, 200, 0);
+
+        let action = state.legal_actions()[0].clone();
+        let visits = tree.result(&action, actor).visits;
+        assert!(visits > 0);
+        tree.advance(&action);
+        state.apply(&action).unwrap();
+        assert_eq!(tree.total_visits(), visits);
+
+        // Searching the new state builds on the kept subtree
+        tree.search(&state, settings(actor), 1, 10, 1);
+        assert_eq!(tree.total_visits(), visits + 10);
+
+        // Moves that weren't searched leave nothing to build on
+        tree.advance(&Action::Throw { card: None });
+        assert_eq!(tree.total_visits(), 0);
+    }
+
+    #[test]
+    fn opponents_pick_their_own_best_reply() {
+        // After 6S, P1 goes out by beating it with AS; taking leaves P1 as the durak
+        let state = endgame(config(2), &["6S 7D", "AS"], 0);
+        let mut tree = SearchTree::new();
+        tree.search(&state, settings(pid(0)), 1, 500, 0);
+
+        tree.advance(&Action::Attack { card: card("6S") });
+        let defend = tree.result(&Action::Defend { pile_index: 0, card: card("AS") }, pid(1));
+        let take = tree.result(&Action::Take, pid(1));
+        assert!(defend.visits > take.visits, "{defend:?} vs {take:?}");
+        assert!(defend.score > take.score);
+    }
+}
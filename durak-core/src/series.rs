@@ -0,0 +1,238 @@
+//! Multi-game matches: a series of deals with cumulative scores.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{EngineError, GameConfig, GameState, Outcome, PlayerId};
+
+/// Who attacks first in the games after the first one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
+#[serde(rename_all = "snake_case")]
+pub enum StartRule {
+    /// The player with the lowest trump starts every game.
+    #[default]
+    LowestTrump,
+    /// The previous durak deals, the player to their left attacks first.
+    LeftOfDurak,
+    /// The player to the previous durak's right attacks first, so the durak defends first.
+    AttackDurak,
+}
+
+/// How points are awarded after each game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
+#[serde(rename_all = "snake_case")]
+pub enum MatchScoring {
+    /// The durak (or every card holder of a losing team) gets one point;
+    /// the first to reach the limit loses the match.
+    #[default]
+    DurakPoint,
+    /// Every player gets one point per player finishing after them;
+    /// the first to reach the limit wins the match.
+    Placement,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
+pub struct MatchConfig {
+    /// Rule for choosing the first attacker of follow-up games.
+    pub start_rule: StartRule,
+    pub scoring: MatchScoring,
+    /// The match ends once any player's score reaches this value.
+    pub score_limit: u32,
+}
+
+impl Default for MatchConfig {
+    fn default() -> Self {
+        Self {
+            start_rule: StartRule::LowestTrump,
+            scoring: MatchScoring::DurakPoint,
+            score_limit: 5,
+        }
+    }
+}
+
+/// A series of computer-dealt games with per-player cumulative scores.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
+pub struct Match {
+    pub config: MatchConfig,
+    /// The game currently being played.
+    pub game: GameState,
+    /// Outcomes of all finished games, oldest first.
+    pub results: Vec<Outcome>,
+    /// Cumulative score per player.
+    pub scores: Vec<u32>,
+}
+
+impl Match {
+    /// Start a match; the first game always uses the lowest trump rule.
+    pub fn new(seed: u64, game_config: GameConfig, config: MatchConfig) -> Result<Self, EngineError> {
+        Ok(Self {
+            config,
+            scores: vec![0; game_config.num_players],
+            game: GameState::new_computer_game(seed, game_config)?,
+            results: vec![],
+        })
+    }
+
+    /// Whether some player has reached the score limit.
+    pub fn is_over(&self) -> bool {
+        self.scores.iter().any(|&s| s >= self.config.score_limit)
+    }
+
+    /// Score the finished current game and deal the next one.
+    /// The last game is kept (and no new game dealt) once the match is over.
+    pub fn next_game(&mut self, seed: u64) -> Result<(), EngineError> {
+        let Some(outcome) = self.game.outcome() else {
+            return Err(EngineError::GameNotFinished);
+        };
+        if self.is_over() {
+            return Err(EngineError::MatchOver);
+        }
+
+        let num_players = self.game.num_players();
+        match self.config.scoring {
+            MatchScoring::DurakPoint => {
+                for loser in outcome.losers() {
+                    self.scores[loser.index()] += 1;
+                }
+            }
+            MatchScoring::Placement => {
+                for (i, score) in self.scores.iter_mut().enumerate() {
+                    let placement = outcome.placement(PlayerId::from_index(i)).unwrap_or(num_players - 1);
+                    *score += (num_players - 1 - placement) as u32;
+                }
+            }
+        }
+
+        let durak = outcome.durak();
+        self.results.push(outcome);
+        if self.is_over() {
+            return Ok(());
+        }
+
+        let game_config = self.game.config.clone();
+        // After a draw there is no durak, so fall back to the lowest trump
+        self.game = match (self.config.start_rule, durak) {
+            (StartRule::LeftOfDurak, Some(durak)) => {
+                GameState::new_computer_game_with_attacker(seed, game_config, durak.next(num_players))
+            }
+            (StartRule::AttackDurak, Some(durak)) => {
+                let attacker = durak.prev(num_players);
+                GameState::new_computer_game_with_attacker(seed, game_config, attacker)
+            }
+            _ => GameState::new_computer_game(seed, game_config),
+        }?;
+        Ok(())
+    }
+
+    /// Players with the most points (the match losers under `DurakPoint`, winners under `Placement`).
+    pub fn leaders(&self) -> Vec<PlayerId> {
+        let max = self.scores.iter().copied().max().unwrap_or(0);
+        (0..self.scores.len())
+            .filter(|&i| self.scores[i] == max)
+            .map(PlayerId::from_index)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::pid;
+
+    fn new_match(num_players: usize, config: MatchConfig) -> Match {
+        Match::new(0, GameConfig { num_players, ..GameConfig::default() }, config).unwrap()
+    }
+
+    /// End the current game: `placements` went out in this order and `losers` keep their cards.
+    fn finish(game: &mut GameState, placements: Vec<Vec<PlayerId>>, losers: &[PlayerId]) {
+        game.stock.clear();
+        game.table.clear();
+        for (i, hand) in game.hands.iter_mut().enumerate() {
+            if !losers.contains(&pid(i)) {
+                hand.clear();
+            }
+        }
+        game.finished = placements;
+    }
+
+    #[test]
+    fn durak_points_add_up_until_the_limit() {
+        let mut m = new_match(3, MatchConfig { score_limit: 2, ..MatchConfig::default() });
+        assert_eq!(m.next_game(1), Err(EngineError::GameNotFinished));
+
+        finish(&mut m.game, vec![vec![pid(0)], vec![pid(1)]], &[pid(2)]);
+        m.next_game(1).unwrap();
+        assert_eq!(m.scores, vec![0, 0, 1]);
+
+        // A draw scores nothing
+        finish(&mut m.game, vec![vec![pid(0)], vec![pid(1), pid(2)]], &[]);
+        m.next_game(2).unwrap();
+        assert_eq!(m.scores, vec![0, 0, 1]);
+        assert!(!m.is_over());
+
+        finish(&mut m.game, vec![vec![pid(1)], vec![pid(0)]], &[pid(2)]);
+        m.next_game(3).unwrap();
+        assert_eq!(m.scores, vec![0, 0, 2]);
+        assert_eq!(m.results.len(), 3);
+        assert!(m.is_over());
+        assert_eq!(m.leaders(), vec![pid(2)]);
+        // The last game is kept
+        assert!(m.game.is_terminal());
+        assert_eq!(m.next_game(4), Err(EngineError::MatchOver));
+    }
+
+    #[test]
+    fn placement_scoring_counts_the_players_behind() {
+        let mut m = new_match(3, MatchConfig { scoring: MatchScoring::Placement, ..MatchConfig::default() });
+        finish(&mut m.game, vec![vec![pid(1)], vec![pid(0)]], &[pid(2)]);
+        m.next_game(1).unwrap();
+        assert_eq!(m.scores, vec![1, 2, 0]);
+
+        // Players going out together share the better placement
+        finish(&mut m.game, vec![vec![pid(0), pid(2)]], &[pid(1)]);
+        m.next_game(2).unwrap();
+        assert_eq!(m.scores, vec![3, 2, 2]);
+        assert_eq!(m.leaders(), vec![pid(0)]);
+    }
+
+    #[test]
+    fn every_member_of_a_losing_team_scores() {
+        let game_config = GameConfig { num_players: 4, teams: vec![0, 1, 0, 1], ..GameConfig::default() };
+        let mut m = Match::new(0, game_config, MatchConfig::default()).unwrap();
+        finish(&mut m.game, vec![vec![pid(0)], vec![pid(2)]], &[pid(1), pid(3)]);
+        assert!(matches!(m.game.outcome(), Some(Outcome::TeamDurak { team: 1, .. })));
+        m.next_game(1).unwrap();
+        assert_eq!(m.scores, vec![0, 1, 0, 1]);
+        assert_eq!(m.leaders(), vec![pid(1), pid(3)]);
+    }
+
+    #[test]
+    fn start_rules_pick_the_next_attacker() {
+        let lowest_trump = GameState::new_computer_game(7, GameConfig { num_players: 4, ..GameConfig::default() })
+            .unwrap()
+            .attacker;
+        let cases = [
+            (StartRule::LowestTrump, lowest_trump, None),
+            (StartRule::LeftOfDurak, pid(2), None),
+            (StartRule::AttackDurak, pid(0), Some(pid(1))),
+        ];
+        for (start_rule, attacker, defender) in cases {
+            let mut m = new_match(4, MatchConfig { start_rule, ..MatchConfig::default() });
+            finish(&mut m.game, vec![vec![pid(0)], vec![pid(2)], vec![pid(3)]], &[pid(1)]);
+            m.next_game(7).unwrap();
+            assert_eq!(m.game.attacker, attacker, "{start_rule:?}");
+            if let Some(defender) = defender {
+                assert_eq!(m.game.defender, defender, "{start_rule:?}");
+            }
+        }
+
+        // Without a durak the lowest trump starts
+        let mut m = new_match(4, MatchConfig { start_rule: StartRule::LeftOfDurak, ..MatchConfig::default() });
+        finish(&mut m.game, vec![vec![pid(0)], vec![pid(1), pid(2), pid(3)]], &[]);
+        m.next_game(7).unwrap();
+        assert_eq!(m.game.attacker, lowest_trump);
+    }
+}
@@ -0,0 +1,45 @@
+//! Fixtures shared by the unit tests.
+
+use crate::{Card, DeckSpec, GameConfig, GameState, PlayerId, Rank};
+
+pub(crate) fn card(notation: &str) -> Card {
+    notation.parse().unwrap()
+}
+
+/// Private cards, e.g. `cards("7C 8D")`.
+pub(crate) fn cards(notation: &str) -> Vec<Card> {
+    notation.split_whitespace().map(|s| s.parse::<Card>().unwrap().as_private()).collect()
+}
+
+pub(crate) fn pid(i: usize) -> PlayerId {
+    PlayerId::from_index(i)
+}
+
+/// The default rules for `num_players`, without reflecting.
+pub(crate) fn config(num_players: usize) -> GameConfig {
+    GameConfig { num_players, trump_reflecting: false, reflecting: false, ..GameConfig::default() }
+}
+
+/// A deck with only the given ranks (no jokers), for positions small enough to follow by hand.
+pub(crate) fn small_deck(ranks: &[Rank], num_players: usize, hand_size: usize) -> GameConfig {
+    GameConfig {
+        custom_deck: Some(DeckSpec { ranks: ranks.to_vec(), jokers: 0 }),
+        num_players,
+        hand_size,
+        ..GameConfig::default()
+    }
+}
+
+/// A position with an empty stock: the given hands, every other card discarded.
+pub(crate) fn endgame(config: GameConfig, hands: &[&str], attacker: usize) -> GameState {
+    let mut state = GameState::new_computer_game_with_attacker(0, config, pid(attacker)).unwrap();
+    state.hands = hands.iter().map(|h| cards(h)).collect();
+    let held: Vec<Card> = state.hands.iter().flatten().copied().collect();
+    state.stock.clear();
+    state.discard = state.unseen(&held);
+    state.voids.clear();
+    state.tricks_played = 1;
+    state.defender = state.next_opponent(pid(attacker));
+    state.attackers = state.build_attackers(pid(attacker));
+    state
+}
@@ -0,0 +1,183 @@
+//! Card counting from one player's point of view: what is still out, and what can't be beaten.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{remove_known, Card, GameState, Location, PlayerId, Suit, ALL_SUITS};
+
+/// Card counts for one suit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
+pub struct SuitCount {
+    pub suit: Suit,
+    /// Cards of this suit the perspective player has not seen, from low to high
+    pub unseen: Vec<Card>,
+    /// Highest card of this suit still held by another player or in the stock
+    pub highest_outstanding: Option<Card>,
+}
+
+/// Card-counting summary for one perspective (see `GameState::tracker`).
+///
+/// Unseen cards are those the perspective player can't see anywhere. Outstanding cards are
+/// all cards still held by other players or in the stock, also when known (e.g. the face-up
+/// trump or a card shown while reflecting). Unseen cards proven to be among discards that
+/// weren't recorded are not outstanding.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(tsify::Tsify))]
+pub struct Tracker {
+    pub perspective: PlayerId,
+    pub trump: Suit,
+    /// Unseen cards per suit (jokers are counted separately)
+    pub suits: Vec<SuitCount>,
+    /// Unseen jokers
+    pub unseen_jokers: Vec<Card>,
+    /// Outstanding trumps, from low to high
+    pub remaining_trumps: Vec<Card>,
+    /// Outstanding jokers
+    pub remaining_jokers: Vec<Card>,
+    /// Cards in the perspective player's hand that no outstanding card beats
+    pub boss_cards: Vec<Card>,
+}
+
+impl GameState {
+    /// Summarize the cards still in play from `perspective`'s point of view.
+    pub fn tracker(&self, perspective: PlayerId) -> Tracker {
+        let mine = &self.hands[perspective.index()];
+
+        let mut known: Vec<Card> = mine.clone();
+        let mut outstanding: Vec<Card> = Vec::new();
+        for (i, hand) in self.hands.iter().enumerate() {
+            if i != perspective.index() {
+                known.extend(hand.iter().filter(|c| c.is_public()));
+                outstanding.extend(hand.iter().filter(|c| c.is_public()));
+            }
+        }
+        known.extend(self.stock.iter().filter(|c| c.is_public()));
+        outstanding.extend(self.stock.iter().filter(|c| c.is_public()));
+        known.extend(&self.discard);
+        for pile in &self.table {
+            known.push(pile.attack);
+            known.extend(pile.defense);
+        }
+
+        let unseen = self.unseen(&known);
+        let discarded = if self.discard.iter().any(|c| c.is_unknown()) {
+            self.deduce(perspective).forced_at(Location::Discard)
+        } else {
+            vec![]
+        };
+        outstanding.extend(remove_known(unseen.clone(), &discarded));
+        outstanding.sort_by_key(|c| c.rank());
+
+        let suits = ALL_SUITS
+            .iter()
+            .map(|&suit| {
+                let mut cards: Vec<Card> =
+                    unseen.iter().filter(|c| !c.is_joker() && c.suit() == suit).copied().collect();
+                cards.sort_by_key(|c| c.rank());
+                SuitCount {
+                    suit,
+                    unseen: cards,
+                    highest_outstanding: outstanding
+                        .iter()
+                        .rev()
+                        .find(|c| !c.is_joker() && c.suit() == suit)
+                        .map(|c| c.as_public()),
+                }
+            })
+            .collect();
+
+        let boss_cards = mine
+            .iter()
+            .filter(|c| !c.is_unknown() && !outstanding.iter().any(|o| o.beats(c, self.trump)))
+            .copied()
+            .collect();
+
+        Tracker {
+            perspective,
+            trump: self.trump,
+            suits,
+            unseen_jokers: unseen.iter().filter(|c| c.is_joker()).copied().collect(),
+            remaining_trumps: outstanding
+                .iter()
+                .filter(|c| c.is_trump(self.trump))
+                .map(|c| c.as_public())
+                .collect(),
+            remaining_jokers: outstanding.iter().filter(|c| c.is_joker()).map(|c| c.as_public()).collect(),
+            boss_cards,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{card, pid, small_deck};
+    use crate::{GameConfig, ManualPositionBuilder, Rank, Void};
+
+    fn unseen(tracker: &Tracker) -> Vec<Card> {
+        tracker.suits.iter().flat_map(|s| s.unseen.clone()).collect()
+    }
+
+    #[test]
+    fn unseen_cards_depend_on_the_perspective() {
+        let state = GameState::new_computer_game(0, GameConfig { num_players: 3, ..GameConfig::default() }).unwrap();
+        for i in 0..3 {
+            let tracker = state.tracker(pid(i));
+            let unseen = unseen(&tracker);
+            // Everything but your own hand and the public cards (the face-up trump, announced trumps)
+            let public = (0..3).filter(|&j| j != i).flat_map(|j| &state.hands[j]).chain(&state.stock);
+            assert_eq!(unseen.len(), 36 - 6 - public.filter(|c| c.is_public()).count());
+            assert!(state.hands[i].iter().all(|c| !unseen.iter().any(|u| u.matches(c))));
+            let others = (0..3).filter(|&j| j != i).flat_map(|j| &state.hands[j]);
+            assert!(others.filter(|c| !c.is_public()).all(|c| unseen.iter().any(|u| u.matches(c))));
+            assert!(tracker.suits.iter().all(|s| s.unseen.windows(2).all(|w| w[0].rank() <= w[1].rank())));
+        }
+    }
+
+    /// Queens, kings and aces for two players.
+    fn queen_to_ace() -> GameConfig {
+        small_deck(&[Rank::Queen, Rank::King, Rank::Ace], 2, 3)
+    }
+
+    #[test]
+    fn boss_cards_after_the_trumps_are_played() {
+        let config = queen_to_ace();
+        let position = ManualPositionBuilder::new(card("AH"), pid(0))
+            .hand(vec![card("AS"), card("KC"), card("QD")])
+            .hand_size(pid(1), 3);
+
+        // Trumps are still out, so nothing is safe
+        let tracker = position.build(config.clone()).unwrap().tracker(pid(0));
+        assert!(tracker.boss_cards.is_empty());
+        assert_eq!(tracker.remaining_trumps, vec![card("QH"), card("KH"), card("AH")]);
+
+        let played = ["AH", "KH", "QH", "QS", "AC", "QC"].map(card).to_vec();
+        let tracker = position.discard(played).build(config).unwrap().tracker(pid(0));
+        assert_eq!(tracker.boss_cards, vec![card("AS"), card("KC")]);
+        assert!(tracker.remaining_trumps.is_empty());
+        assert_eq!(unseen(&tracker), vec![card("KD"), card("AD"), card("KS")]);
+        let diamonds = tracker.suits.iter().find(|s| s.suit == Suit::Diamonds).unwrap();
+        assert_eq!(diamonds.highest_outstanding, Some(card("AD")));
+    }
+
+    #[test]
+    fn cards_proven_discarded_are_not_outstanding() {
+        let config = queen_to_ace();
+        // One of the six discards wasn't recorded
+        let mut state = ManualPositionBuilder::new(card("AH"), pid(0))
+            .hand(vec![card("QD"), card("KD"), card("KC")])
+            .hand_size(pid(1), 3)
+            .discard(["AH", "KH", "QH", "AC", "QC"].map(card).to_vec())
+            .build(config)
+            .unwrap();
+        assert_eq!(state.tracker(pid(0)).boss_cards, vec![card("KC")]);
+
+        // P1 has no diamonds, so AD is the unrecorded discard
+        state.voids.push(Void { player: pid(1), suit: Suit::Diamonds, below: None });
+        let tracker = state.tracker(pid(0));
+        assert_eq!(tracker.boss_cards, vec![card("QD"), card("KD"), card("KC")]);
+        assert_eq!(unseen(&tracker), vec![card("AD"), card("QS"), card("KS"), card("AS")]);
+        let diamonds = tracker.suits.iter().find(|s| s.suit == Suit::Diamonds).unwrap();
+        assert_eq!(diamonds.highest_outstanding, None);
+    }
+}
@@ -1,8 +1,9 @@
-use durak_core::{mcts_evaluate_actions, pick_random_action as core_pick_random_action, Action, Card, GameState, PlayerId, Rank};
+use durak_core::{mcts_evaluate_actions, pick_random_action as core_pick_random_action, Action, Card, Edit, EngineError, GameState, MCTSObjective, ManualPositionBuilder, PlayerId, SearchSettings, SearchTree, TrumpDeclaration};
 
 /// Default maximum search depth for MCTS simulations.
 const DEFAULT_MAX_DEPTH: u32 = 100;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
@@ -11,193 +12,310 @@ pub fn init_panic_hook() {
     // Keeping deps minimal in backbone.
 }
 
+/// TypeScript declaration of the errors thrown for rejected engine calls (see `engine_error`).
+#[wasm_bindgen(typescript_custom_section)]
+const TS_ENGINE_ERROR: &str = r#"
+/** A rejected engine call: the error with its stable `code`, plus a readable message. */
+export type JsEngineError = EngineError & { message: string };
+"#;
+
+/// Convert an engine error into a JS object `{ code, message, ...details }`,
+/// where `code` is the stable snake_case error code (e.g. `"does_not_beat"`).
+fn engine_error(error: EngineError) -> JsValue {
+    #[derive(Serialize)]
+    struct JsEngineError<'a> {
+        #[serde(flatten)]
+        error: &'a EngineError,
+        message: String,
+    }
+
+    let message = error.to_string();
+    let js = JsEngineError { error: &error, message: message.clone() };
+    js.serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .unwrap_or_else(|_| JsValue::from_str(&message))
+}
+
+/// Parse a game config, using the default config when none is given.
+/// A malformed config is an error rather than silently replaced by the default.
+fn parse_config(config_json: JsValue) -> Result<durak_core::GameConfig, JsValue> {
+    if config_json.is_undefined() || config_json.is_null() {
+        return Ok(durak_core::GameConfig::default());
+    }
+    Ok(serde_wasm_bindgen::from_value(config_json)?)
+}
+
+/// Parse a game state, rejecting states that are inconsistent (see `GameState::validate`),
+/// so that a corrupted saved game is reported as an error instead of making the engine panic.
+fn parse_state(state_json: JsValue) -> Result<GameState, JsValue> {
+    let mut state: GameState = serde_wasm_bindgen::from_value(state_json)?;
+    load_state(&mut state)?;
+    Ok(state)
+}
+
+/// Bring a deserialized state up to date with older saves and validate it.
+fn load_state(state: &mut GameState) -> Result<(), JsValue> {
+    state.restore_trump_card();
+    state.validate().map_err(engine_error)
+}
+
 /// Request for creating a new computer game.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, tsify::Tsify)]
 pub struct NewComputerGameRequest {
     /// Seed for random card dealing.
     pub seed: u64,
 }
 
 /// Create a new computer game with cards dealt by Rust.
-#[wasm_bindgen]
-pub fn new_computer_game(req_json: JsValue, config_json: JsValue) -> Result<JsValue, JsValue> {
+#[wasm_bindgen(unchecked_return_type = "GameState")]
+pub fn new_computer_game(
+    #[wasm_bindgen(unchecked_param_type = "NewComputerGameRequest")] req_json: JsValue,
+    #[wasm_bindgen(unchecked_param_type = "GameConfig | undefined")] config_json: JsValue,
+) -> Result<JsValue, JsValue> {
     let req: NewComputerGameRequest = serde_wasm_bindgen::from_value(req_json)?;
-    let config: durak_core::GameConfig = serde_wasm_bindgen::from_value(config_json)
-        .unwrap_or_default();
-    let state = GameState::new_computer_game(req.seed, config);
+    let config = parse_config(config_json)?;
+    let state = GameState::new_computer_game(req.seed, config)
+        .map_err(engine_error)?;
     Ok(serde_wasm_bindgen::to_value(&state).unwrap())
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, tsify::Tsify)]
 pub struct ManualStartRequest {
     pub trump_card: Card,
     pub player_hand: Vec<Card>,
+    /// Your seat (default 0).
+    #[serde(default)]
+    pub seat: u8,
     pub starting_player: u8,
-    /// Opponent declared trumps: list of (PlayerId, Option<Rank>).
-    /// None rank means opponent has no trump / didn't declare.
+    /// What opponents declared of their trumps: their lowest trump or that they have none.
+    /// Opponents who are left out declared nothing.
     #[serde(default)]
-    pub opponent_trumps: Vec<(PlayerId, Option<Rank>)>,
+    pub opponent_trumps: Vec<(PlayerId, TrumpDeclaration)>,
 }
 
-#[wasm_bindgen]
-pub fn new_manual_game(req_json: JsValue, config_json: JsValue) -> Result<JsValue, JsValue> {
+#[wasm_bindgen(unchecked_return_type = "GameState")]
+pub fn new_manual_game(
+    #[wasm_bindgen(unchecked_param_type = "ManualStartRequest")] req_json: JsValue,
+    #[wasm_bindgen(unchecked_param_type = "GameConfig | undefined")] config_json: JsValue,
+) -> Result<JsValue, JsValue> {
     let req: ManualStartRequest = serde_wasm_bindgen::from_value(req_json)?;
-    let config: durak_core::GameConfig = serde_wasm_bindgen::from_value(config_json)
-        .unwrap_or_default();
+    let config = parse_config(config_json)?;
     let s = GameState::new_manual_game(
         req.trump_card,
         req.player_hand,
+        req.seat,
         req.starting_player,
         req.opponent_trumps,
         config,
     )
-    .map_err(|e| JsValue::from_str(&e.to_string()))?;
+    .map_err(engine_error)?;
+    Ok(serde_wasm_bindgen::to_value(&s).unwrap())
+}
+
+/// Create a manual game from a position in progress (see `ManualPositionBuilder`).
+#[wasm_bindgen(unchecked_return_type = "GameState")]
+pub fn new_manual_position(
+    #[wasm_bindgen(unchecked_param_type = "ManualPositionBuilder")] position_json: JsValue,
+    #[wasm_bindgen(unchecked_param_type = "GameConfig | undefined")] config_json: JsValue,
+) -> Result<JsValue, JsValue> {
+    let position: ManualPositionBuilder = serde_wasm_bindgen::from_value(position_json)?;
+    let config = parse_config(config_json)?;
+    let s = position.build(config).map_err(engine_error)?;
     Ok(serde_wasm_bindgen::to_value(&s).unwrap())
 }
 
 /// Get legal actions for a game state.
-#[wasm_bindgen]
-pub fn legal_actions(state_json: JsValue) -> Result<JsValue, JsValue> {
-    let state: GameState = serde_wasm_bindgen::from_value(state_json)?;
+#[wasm_bindgen(unchecked_return_type = "Action[]")]
+pub fn legal_actions(
+    #[wasm_bindgen(unchecked_param_type = "GameState")] state_json: JsValue,
+) -> Result<JsValue, JsValue> {
+    let state = parse_state(state_json)?;
     let acts = state.legal_actions();
     Ok(serde_wasm_bindgen::to_value(&acts).unwrap())
 }
 
 /// Apply an action to a game state.
-#[wasm_bindgen]
-pub fn apply_action(state_json: JsValue, action_json: JsValue) -> Result<JsValue, JsValue> {
-    let mut state: GameState = serde_wasm_bindgen::from_value(state_json)?;
+#[wasm_bindgen(unchecked_return_type = "GameState")]
+pub fn apply_action(
+    #[wasm_bindgen(unchecked_param_type = "GameState")] state_json: JsValue,
+    #[wasm_bindgen(unchecked_param_type = "Action")] action_json: JsValue,
+) -> Result<JsValue, JsValue> {
+    let mut state = parse_state(state_json)?;
     let action: Action = serde_wasm_bindgen::from_value(action_json)?;
-    state.apply(&action).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    state.apply(&action).map_err(engine_error)?;
     Ok(serde_wasm_bindgen::to_value(&state).unwrap())
 }
 
-/// Get the actor to move for a game state.
-#[wasm_bindgen]
-pub fn actor_to_move(state_json: JsValue) -> Result<JsValue, JsValue> {
-    let state: GameState = serde_wasm_bindgen::from_value(state_json)?;
-    let actor = state.actor_to_move();
-    Ok(serde_wasm_bindgen::to_value(&actor).unwrap())
+/// Apply a manual-mode correction (see `Edit`), re-validating the state.
+#[wasm_bindgen(unchecked_return_type = "GameState")]
+pub fn edit_state(
+    #[wasm_bindgen(unchecked_param_type = "GameState")] state_json: JsValue,
+    #[wasm_bindgen(unchecked_param_type = "Edit")] edit_json: JsValue,
+) -> Result<JsValue, JsValue> {
+    let mut state = parse_state(state_json)?;
+    let edit: Edit = serde_wasm_bindgen::from_value(edit_json)?;
+    state.edit(&edit).map_err(engine_error)?;
+    Ok(serde_wasm_bindgen::to_value(&state).unwrap())
 }
 
-/// Check if all cards are deducible from the human player's (P0) perspective.
-/// All cards are deducible if:
-/// 1. Stock is empty
-/// 2. At most one opponent has non-public cards (can be deduced by elimination)
-#[wasm_bindgen]
-pub fn all_cards_deducible(state_json: JsValue) -> Result<JsValue, JsValue> {
-    let state: GameState = serde_wasm_bindgen::from_value(state_json)?;
-
-    // Stock must be empty
-    if !state.stock.is_empty() {
-        return Ok(serde_wasm_bindgen::to_value(&false).unwrap());
-    }
-
-    // Count opponents (non-P0) who have at least one non-public card
-    let mut opponents_with_hidden = 0;
-    for (i, hand) in state.hands.iter().enumerate() {
-        if i == 0 {
-            continue; // Skip human player (P0)
-        }
-        let has_non_public = hand.iter().any(|c| !c.is_public());
-        if has_non_public {
-            opponents_with_hidden += 1;
-        }
-    }
-
-    // Deducible if at most 1 opponent has hidden cards
-    let deducible = opponents_with_hidden <= 1;
-    Ok(serde_wasm_bindgen::to_value(&deducible).unwrap())
+/// Record that `player` is seen holding `card` (e.g. a card flashed by mistake or remembered from a take).
+#[wasm_bindgen(unchecked_return_type = "GameState")]
+pub fn record_held_card(
+    #[wasm_bindgen(unchecked_param_type = "GameState")] state_json: JsValue,
+    #[wasm_bindgen(unchecked_param_type = "PlayerId")] player_json: JsValue,
+    #[wasm_bindgen(unchecked_param_type = "Card")] card_json: JsValue,
+) -> Result<JsValue, JsValue> {
+    let mut state = parse_state(state_json)?;
+    let player: PlayerId = serde_wasm_bindgen::from_value(player_json)?;
+    let card: Card = serde_wasm_bindgen::from_value(card_json)?;
+    state.edit(&Edit::Reveal { player, card }).map_err(engine_error)?;
+    Ok(serde_wasm_bindgen::to_value(&state).unwrap())
 }
 
-/// Deduce all unknown cards and return state with them revealed.
-/// Only works when all_cards_deducible would return true.
-/// Unknown cards are replaced with Public cards deduced by elimination.
-#[wasm_bindgen]
-pub fn deduce_cards(state_json: JsValue) -> Result<JsValue, JsValue> {
-    use std::collections::HashSet;
-
-    let mut state: GameState = serde_wasm_bindgen::from_value(state_json)?;
-
-    // Collect all known cards (suit, rank)
-    let mut known: HashSet<(durak_core::Suit, Rank)> = HashSet::new();
+/// Record that `player` drew the face-up trump card.
+#[wasm_bindgen(unchecked_return_type = "GameState")]
+pub fn record_trump_draw(
+    #[wasm_bindgen(unchecked_param_type = "GameState")] state_json: JsValue,
+    #[wasm_bindgen(unchecked_param_type = "PlayerId")] player_json: JsValue,
+) -> Result<JsValue, JsValue> {
+    let mut state = parse_state(state_json)?;
+    let player: PlayerId = serde_wasm_bindgen::from_value(player_json)?;
+    state.edit(&Edit::DrewTrump { player }).map_err(engine_error)?;
+    Ok(serde_wasm_bindgen::to_value(&state).unwrap())
+}
 
-    // Cards in hands (public or private)
-    for hand in &state.hands {
-        for card in hand {
-            if card.is_public() || card.is_private() {
-                known.insert((card.suit(), card.rank()));
-            }
-        }
-    }
+/// Apply an action submitted by `player`, rejecting it if that player may not act now.
+#[wasm_bindgen(unchecked_return_type = "GameState")]
+pub fn apply_action_as(
+    #[wasm_bindgen(unchecked_param_type = "GameState")] state_json: JsValue,
+    #[wasm_bindgen(unchecked_param_type = "PlayerId")] player_json: JsValue,
+    #[wasm_bindgen(unchecked_param_type = "Action")] action_json: JsValue,
+) -> Result<JsValue, JsValue> {
+    let mut state = parse_state(state_json)?;
+    let player: PlayerId = serde_wasm_bindgen::from_value(player_json)?;
+    let action: Action = serde_wasm_bindgen::from_value(action_json)?;
+    state.apply_as(player, &action).map_err(engine_error)?;
+    Ok(serde_wasm_bindgen::to_value(&state).unwrap())
+}
 
-    // Cards on table
-    for pile in &state.table {
-        known.insert((pile.attack.suit(), pile.attack.rank()));
-        if let Some(d) = pile.defense {
-            known.insert((d.suit(), d.rank()));
-        }
-    }
+/// Get the players who may act right now (the actor to move first).
+#[wasm_bindgen(unchecked_return_type = "PlayerId[]")]
+pub fn eligible_players(
+    #[wasm_bindgen(unchecked_param_type = "GameState")] state_json: JsValue,
+) -> Result<JsValue, JsValue> {
+    let state = parse_state(state_json)?;
+    Ok(serde_wasm_bindgen::to_value(&state.eligible_players()).unwrap())
+}
 
-    // Cards in discard
-    for card in &state.discard {
-        known.insert((card.suit(), card.rank()));
-    }
+/// Get the actor to move for a game state.
+#[wasm_bindgen(unchecked_return_type = "PlayerId")]
+pub fn actor_to_move(
+    #[wasm_bindgen(unchecked_param_type = "GameState")] state_json: JsValue,
+) -> Result<JsValue, JsValue> {
+    let state = parse_state(state_json)?;
+    let actor = state.actor_to_move();
+    Ok(serde_wasm_bindgen::to_value(&actor).unwrap())
+}
 
-    // Cards in stock (public ones like trump)
-    for card in &state.stock {
-        if card.is_public() {
-            known.insert((card.suit(), card.rank()));
-        }
-    }
+/// Check if the location of every card is known from `perspective`'s point of view,
+/// by deduction from hand counts, played cards and proven voids.
+#[wasm_bindgen(unchecked_return_type = "boolean")]
+pub fn all_cards_deducible(
+    #[wasm_bindgen(unchecked_param_type = "GameState")] state_json: JsValue,
+    #[wasm_bindgen(unchecked_param_type = "PlayerId")] perspective_json: JsValue,
+) -> Result<JsValue, JsValue> {
+    let state = parse_state(state_json)?;
+    let perspective: PlayerId = serde_wasm_bindgen::from_value(perspective_json)?;
+    perspective.validate(state.num_players()).map_err(engine_error)?;
+    let deducible = state.deduce(perspective).is_complete();
+    Ok(serde_wasm_bindgen::to_value(&deducible).unwrap())
+}
 
-    // Calculate remaining cards (unknown)
-    let mut remaining: Vec<Card> = durak_core::full_deck(state.config.deck_size)
-        .into_iter()
-        .filter(|c| !known.contains(&(c.suit(), c.rank())))
-        .map(|c| c.as_public())
-        .collect();
+/// Reveal every unknown card whose location is proven from `perspective`'s point of view.
+/// Deduced cards become Public; cards that could still be in several places stay Unknown.
+#[wasm_bindgen(unchecked_return_type = "GameState")]
+pub fn deduce_cards(
+    #[wasm_bindgen(unchecked_param_type = "GameState")] state_json: JsValue,
+    #[wasm_bindgen(unchecked_param_type = "PlayerId")] perspective_json: JsValue,
+) -> Result<JsValue, JsValue> {
+    let mut state = parse_state(state_json)?;
+    let perspective: PlayerId = serde_wasm_bindgen::from_value(perspective_json)?;
+    perspective.validate(state.num_players()).map_err(engine_error)?;
+    state.reveal_deduced(perspective);
+    Ok(serde_wasm_bindgen::to_value(&state).unwrap())
+}
 
-    // Replace Unknown cards in hands with remaining cards
-    for hand in &mut state.hands {
-        for card in hand.iter_mut() {
-            if card.is_unknown() {
-                if let Some(deduced) = remaining.pop() {
-                    *card = deduced;
-                }
-            }
-        }
-    }
+/// Get the card-location deduction from `perspective`'s point of view.
+#[wasm_bindgen(unchecked_return_type = "Deduction")]
+pub fn deduce(
+    #[wasm_bindgen(unchecked_param_type = "GameState")] state_json: JsValue,
+    #[wasm_bindgen(unchecked_param_type = "PlayerId")] perspective_json: JsValue,
+) -> Result<JsValue, JsValue> {
+    let state = parse_state(state_json)?;
+    let perspective: PlayerId = serde_wasm_bindgen::from_value(perspective_json)?;
+    perspective.validate(state.num_players()).map_err(engine_error)?;
+    let deduction = state.deduce(perspective);
+    Ok(serde_wasm_bindgen::to_value(&deduction).unwrap())
+}
 
-    // Replace Unknown cards in stock (if any)
-    for card in &mut state.stock {
-        if card.is_unknown() {
-            if let Some(deduced) = remaining.pop() {
-                *card = deduced;
-            }
-        }
-    }
+/// Get the chance of each hidden place (opponent hands, stock) holding each card unseen by
+/// `perspective`. Exact for small cases, otherwise estimated from `samples` random deals.
+#[wasm_bindgen(unchecked_return_type = "CardProbabilities")]
+pub fn card_probabilities(
+    #[wasm_bindgen(unchecked_param_type = "GameState")] state_json: JsValue,
+    #[wasm_bindgen(unchecked_param_type = "PlayerId")] perspective_json: JsValue,
+    samples: u32,
+) -> Result<JsValue, JsValue> {
+    use rand::{rngs::StdRng, SeedableRng};
+    let state = parse_state(state_json)?;
+    let perspective: PlayerId = serde_wasm_bindgen::from_value(perspective_json)?;
+    perspective.validate(state.num_players()).map_err(engine_error)?;
+    let seed = (js_sys::Math::random() * 1_000_000_000.0) as u64;
+    let mut rng = StdRng::seed_from_u64(seed);
+    let probabilities = state.card_probabilities(perspective, samples as usize, &mut rng);
+    Ok(serde_wasm_bindgen::to_value(&probabilities).unwrap())
+}
 
-    Ok(serde_wasm_bindgen::to_value(&state).unwrap())
+/// Get the card-counting summary from `perspective`'s point of view: unseen cards per suit,
+/// remaining trumps, the highest outstanding card per suit and unbeatable cards in hand.
+#[wasm_bindgen(unchecked_return_type = "Tracker")]
+pub fn tracker(
+    #[wasm_bindgen(unchecked_param_type = "GameState")] state_json: JsValue,
+    #[wasm_bindgen(unchecked_param_type = "PlayerId")] perspective_json: JsValue,
+) -> Result<JsValue, JsValue> {
+    let state = parse_state(state_json)?;
+    let perspective: PlayerId = serde_wasm_bindgen::from_value(perspective_json)?;
+    perspective.validate(state.num_players()).map_err(engine_error)?;
+    Ok(serde_wasm_bindgen::to_value(&state.tracker(perspective)).unwrap())
 }
 
 /// Get the loser (durak) of the game, or null if game is not over.
-#[wasm_bindgen]
-pub fn get_durak(state_json: JsValue) -> Result<JsValue, JsValue> {
-    let state: GameState = serde_wasm_bindgen::from_value(state_json)?;
+#[wasm_bindgen(unchecked_return_type = "PlayerId | undefined")]
+pub fn get_durak(
+    #[wasm_bindgen(unchecked_param_type = "GameState")] state_json: JsValue,
+) -> Result<JsValue, JsValue> {
+    let state = parse_state(state_json)?;
     Ok(serde_wasm_bindgen::to_value(&state.durak()).unwrap())
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Get the outcome (durak or draw, with finishing order), or null if game is not over.
+#[wasm_bindgen(unchecked_return_type = "Outcome | undefined")]
+pub fn get_outcome(
+    #[wasm_bindgen(unchecked_param_type = "GameState")] state_json: JsValue,
+) -> Result<JsValue, JsValue> {
+    let state = parse_state(state_json)?;
+    Ok(serde_wasm_bindgen::to_value(&state.outcome()).unwrap())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, tsify::Tsify)]
 pub struct ActionAggregate {
     pub action: Action,
     /// Total number of visits (rollouts) across all determinizations.
     pub visits: u32,
-    /// Score: wins / visits. Range [0, 1].
+    /// Score: reward / visits. Range [0, 1].
     pub score: f32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, tsify::Tsify)]
 pub struct SolveAggregate {
     pub determinizations: u32,
     /// Total visits across all determinizations.
@@ -207,11 +325,9 @@ pub struct SolveAggregate {
     pub actions: Vec<ActionAggregate>,
 }
 
-/// Unified solve request.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct UnifiedSolveRequest {
-    /// Game state.
-    pub state: GameState,
+/// Search settings for a solve.
+#[derive(Debug, Clone, Serialize, Deserialize, tsify::Tsify)]
+pub struct SolveOptions {
     /// Number of determinizations to run.
     #[serde(default = "default_determinizations")]
     pub determinizations: u32,
@@ -221,6 +337,33 @@ pub struct UnifiedSolveRequest {
     /// Maximum search depth for rollouts.
     #[serde(default)]
     pub max_depth: Option<u32>,
+    /// What the search optimizes (not being durak, or expected placement).
+    #[serde(default)]
+    pub objective: MCTSObjective,
+    /// Player whose knowledge is used to deal the hidden cards (default: the actor to move).
+    #[serde(default)]
+    pub perspective: Option<PlayerId>,
+}
+
+impl Default for SolveOptions {
+    fn default() -> Self {
+        Self {
+            determinizations: default_determinizations(),
+            rollouts_per_determinization: None,
+            max_depth: None,
+            objective: MCTSObjective::default(),
+            perspective: None,
+        }
+    }
+}
+
+/// Unified solve request.
+#[derive(Debug, Clone, Serialize, Deserialize, tsify::Tsify)]
+pub struct UnifiedSolveRequest {
+    /// Game state.
+    pub state: GameState,
+    #[serde(flatten)]
+    pub options: SolveOptions,
 }
 
 fn default_determinizations() -> u32 { 1 }
@@ -231,86 +374,85 @@ fn normalize_action_key(action: &Action) -> String {
     match action {
         Action::Attack { card } => format!("attack:{}:{}", card.suit() as u8, card.rank() as u8),
         Action::PassAttack => "pass_attack".to_string(),
+        Action::AttackMany { cards } => format!("attack_many:{}", cards_key(cards)),
         Action::Defend { pile_index, card } => format!("defend:{}:{}:{}", pile_index, card.suit() as u8, card.rank() as u8),
         Action::Take => "take".to_string(),
         Action::Throw { card: Some(c) } => format!("throw:{}:{}", c.suit() as u8, c.rank() as u8),
         Action::Throw { card: None } => "throw:done".to_string(),
-        Action::Reflect { card } => format!("reflect:{}:{}", card.suit() as u8, card.rank() as u8),
-        Action::ReflectTrump { card } => format!("reflect_trump:{}:{}", card.suit() as u8, card.rank() as u8),
+        Action::ThrowMany { cards } => format!("throw_many:{}", cards_key(cards)),
+        Action::Reflect { card, to } => format!("reflect:{}:{}:{:?}", card.suit() as u8, card.rank() as u8, to),
+        Action::ReflectTrump { card, to } => format!("reflect_trump:{}:{}:{:?}", card.suit() as u8, card.rank() as u8, to),
+        Action::ExchangeTrump => "exchange_trump".to_string(),
     }
 }
 
-/// Unified solve function using UCT-MCTS tree search.
-#[wasm_bindgen]
-pub fn solve(req_json: JsValue) -> Result<JsValue, JsValue> {
-    let req: UnifiedSolveRequest = serde_wasm_bindgen::from_value(req_json)?;
-
-    // Get perspective from actor to move
-    let perspective = req.state.actor_to_move();
-
-    // Get legal actions
-    let root_actions = req.state.legal_actions();
-    if root_actions.is_empty() {
-        let out = SolveAggregate {
-            determinizations: req.determinizations,
-            total_visits: 0,
-            best_score: 0.0,
-            actions: vec![],
-        };
-        return Ok(serde_wasm_bindgen::to_value(&out).unwrap());
-    }
+/// Order-independent key for a set of cards, ignoring visibility.
+fn cards_key(cards: &[Card]) -> String {
+    let mut keys: Vec<String> = cards.iter().map(|c| format!("{}:{}", c.suit() as u8, c.rank() as u8)).collect();
+    keys.sort();
+    keys.join(",")
+}
 
-    // Track results per action across all determinizations
-    use std::collections::HashMap;
+#[derive(Debug, Clone, Default)]
+struct ActionStats {
+    visits: u32,
+    weighted_score: f32, // sum of (visits * score) for weighted average
+}
 
-    #[derive(Default)]
-    struct ActionStats {
-        visits: u32,
-        weighted_score: f32, // sum of (visits * score) for weighted average
-    }
+/// Search results for one state, summed over determinizations that are each searched with their
+/// own tree. Only the visits and scores of the root actions are kept, not the search trees.
+#[derive(Debug, Clone, Default)]
+struct SearchStats {
+    determinizations: u32,
+    total_visits: u32,
+    /// Stats per action, by normalized action key
+    actions: HashMap<String, ActionStats>,
+}
 
-    let mut action_stats: HashMap<String, ActionStats> = HashMap::new();
-    let mut total_visits = 0u32;
+impl SearchStats {
+    /// Run UCT-MCTS on `options.determinizations` determinizations of `state`, adding to the stats.
+    fn search(&mut self, state: &GameState, options: &SolveOptions) -> Result<(), JsValue> {
+        use rand::{rngs::StdRng, SeedableRng};
 
-    // Initialize stats for all root actions using normalized keys
-    for action in &root_actions {
-        let key = normalize_action_key(action);
-        action_stats.insert(key, ActionStats::default());
-    }
+        // Search for the actor to move, dealing hidden cards from the given perspective
+        let actor = state.actor_to_move();
+        let perspective = options.perspective.unwrap_or(actor);
+        perspective.validate(state.num_players()).map_err(engine_error)?;
 
-    let max_depth = req.max_depth.unwrap_or(DEFAULT_MAX_DEPTH);
-    let rollouts = req.rollouts_per_determinization.unwrap_or(1000);
+        let max_depth = options.max_depth.unwrap_or(DEFAULT_MAX_DEPTH);
+        let rollouts = options.rollouts_per_determinization.unwrap_or(1000);
+        self.determinizations += options.determinizations;
 
-    // Run MCTS for each determinization
-    for i in 0..req.determinizations {
-        use rand::{rngs::StdRng, SeedableRng};
-        let seed = (js_sys::Math::random() * 1_000_000_000.0) as u64 + i as u64;
-        let mut rng = StdRng::seed_from_u64(seed);
+        // Run MCTS for each determinization
+        for i in 0..options.determinizations {
+            let seed = (js_sys::Math::random() * 1_000_000_000.0) as u64 + i as u64;
+            let mut rng = StdRng::seed_from_u64(seed);
 
-        // Determinize the state (assign random cards to unknown slots and stock)
-        let det_state = req.state.determinize(perspective, &mut rng);
+            // Determinize the state (assign random cards to unknown slots and stock)
+            let det_state = state.determinize(perspective, &mut rng);
 
-        // Run MCTS on the determinized state
-        let eval = mcts_evaluate_actions(&det_state, seed, perspective, rollouts, max_depth, 1.41);
+            // Run MCTS on the determinized state
+            let eval = mcts_evaluate_actions(&det_state, seed, actor, rollouts, max_depth, 1.41, options.objective);
 
-        total_visits += eval.total_rollouts;
+            self.total_visits += eval.total_rollouts;
 
-        // Record results for each action using normalized keys
-        for result in eval.actions {
-            let key = normalize_action_key(&result.action);
-            if let Some(stats) = action_stats.get_mut(&key) {
+            // Record results for each action using normalized keys
+            for result in eval.actions {
+                let stats = self.actions.entry(normalize_action_key(&result.action)).or_default();
                 stats.visits += result.visits;
                 stats.weighted_score += result.visits as f32 * result.score;
             }
         }
+        Ok(())
     }
 
-    // Build the result using normalized keys
-    let mut actions: Vec<ActionAggregate> = root_actions
-        .into_iter()
-        .filter_map(|action| {
-            let key = normalize_action_key(&action);
-            action_stats.get(&key).map(|stats| {
+    /// Summarize the stats for the legal actions of `state`, best first.
+    fn aggregate(&self, state: &GameState) -> SolveAggregate {
+        let mut actions: Vec<ActionAggregate> = state
+            .legal_actions()
+            .into_iter()
+            .map(|action| {
+                let stats = self.actions.get(&normalize_action_key(&action)).cloned().unwrap_or_default();
                 let score = if stats.visits > 0 {
                     stats.weighted_score / stats.visits as f32
                 } else {
@@ -322,6 +464,48 @@ pub fn solve(req_json: JsValue) -> Result<JsValue, JsValue> {
                     score,
                 }
             })
+            .collect();
+
+        // Sort by score descending (best actions first)
+        actions.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+
+        let best_score = actions.first().map(|a| a.score).unwrap_or(0.0);
+
+        SolveAggregate {
+            determinizations: self.determinizations,
+            total_visits: self.total_visits,
+            best_score,
+            actions,
+        }
+    }
+}
+
+/// Search `state` for the actor to move, adding to `tree` (see `SearchTree::search`).
+fn run_search(tree: &mut SearchTree, state: &GameState, options: &SolveOptions) -> Result<(), JsValue> {
+    // Search for the actor to move, dealing hidden cards from the given perspective
+    let perspective = options.perspective.unwrap_or_else(|| state.actor_to_move());
+    perspective.validate(state.num_players()).map_err(engine_error)?;
+
+    let settings = SearchSettings {
+        objective: options.objective,
+        perspective,
+        max_depth: options.max_depth.unwrap_or(DEFAULT_MAX_DEPTH),
+    };
+    let rollouts = options.rollouts_per_determinization.unwrap_or(1000);
+    let seed = (js_sys::Math::random() * 1_000_000_000.0) as u64;
+    tree.search(state, settings, options.determinizations, rollouts, seed);
+    Ok(())
+}
+
+/// Summarize the search results for the legal actions of `state`, best first.
+fn aggregate_tree(tree: &SearchTree, state: &GameState) -> SolveAggregate {
+    let actor = state.actor_to_move();
+    let mut actions: Vec<ActionAggregate> = state
+        .legal_actions()
+        .iter()
+        .map(|action| {
+            let result = tree.result(action, actor);
+            ActionAggregate { action: result.action, visits: result.visits, score: result.score }
         })
         .collect();
 
@@ -330,22 +514,303 @@ pub fn solve(req_json: JsValue) -> Result<JsValue, JsValue> {
 
     let best_score = actions.first().map(|a| a.score).unwrap_or(0.0);
 
-    let out = SolveAggregate {
-        determinizations: req.determinizations,
-        total_visits,
+    SolveAggregate {
+        determinizations: tree.determinizations(),
+        total_visits: tree.total_visits(),
         best_score,
         actions,
-    };
+    }
+}
+
+/// Unified solve function using UCT-MCTS tree search.
+#[wasm_bindgen(unchecked_return_type = "SolveAggregate")]
+pub fn solve(
+    #[wasm_bindgen(unchecked_param_type = "UnifiedSolveRequest")] req_json: JsValue,
+) -> Result<JsValue, JsValue> {
+    let mut req: UnifiedSolveRequest = serde_wasm_bindgen::from_value(req_json)?;
+    load_state(&mut req.state)?;
+
+    let mut stats = SearchStats::default();
+    if req.state.legal_actions().is_empty() {
+        stats.determinizations = req.options.determinizations;
+    } else {
+        stats.search(&req.state, &req.options)?;
+    }
 
-    Ok(serde_wasm_bindgen::to_value(&out).unwrap())
+    Ok(serde_wasm_bindgen::to_value(&stats.aggregate(&req.state)).unwrap())
 }
 
 /// Pick a random legal action from the given state.
 /// Returns null if no legal actions are available (terminal state).
-#[wasm_bindgen]
-pub fn pick_random_action(state_json: JsValue) -> Result<JsValue, JsValue> {
-    let state: GameState = serde_wasm_bindgen::from_value(state_json)?;
+#[wasm_bindgen(unchecked_return_type = "Action | undefined")]
+pub fn pick_random_action(
+    #[wasm_bindgen(unchecked_param_type = "GameState")] state_json: JsValue,
+) -> Result<JsValue, JsValue> {
+    let state = parse_state(state_json)?;
     let seed = (js_sys::Math::random() * 1_000_000_000.0) as u64;
     let action = core_pick_random_action(&state, seed);
     Ok(serde_wasm_bindgen::to_value(&action).unwrap())
 }
+
+/// A change made to a session's game, as recorded in its history.
+#[derive(Debug, Clone, Serialize, Deserialize, tsify::Tsify)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SessionStep {
+    /// An action played by `player` (the actor to move, or an eligible player jumping in).
+    Action { player: PlayerId, action: Action },
+    /// A manual-mode correction.
+    Edit { edit: Edit },
+}
+
+/// A saved session: the current state with the history that led to it.
+#[derive(Debug, Clone, Serialize, Deserialize, tsify::Tsify)]
+pub struct SessionSnapshot {
+    pub state: GameState,
+    /// States before each step, oldest first (for undo).
+    #[serde(default)]
+    pub history: Vec<(GameState, SessionStep)>,
+    /// Undone steps, most recently undone last (for redo).
+    #[serde(default)]
+    pub redo: Vec<SessionStep>,
+}
+
+/// A game owned by Rust, so the state isn't converted from and to JS on every call.
+/// Keeps the move history for undo/redo and the search tree of the current state, which carries
+/// over to the next state when the actor to move plays a move.
+#[wasm_bindgen]
+pub struct GameSession {
+    state: GameState,
+    history: Vec<(GameState, SessionStep)>,
+    redo: Vec<SessionStep>,
+    search: SearchTree,
+}
+
+impl GameSession {
+    fn from_state(state: GameState) -> Self {
+        Self { state, history: vec![], redo: vec![], search: SearchTree::new() }
+    }
+
+    /// Apply a step to the current state, recording it for undo.
+    fn apply_step(&mut self, step: SessionStep) -> Result<(), EngineError> {
+        let before = self.state.clone();
+        match &step {
+            SessionStep::Action { player, action } => self.state.apply_as(*player, action)?,
+            SessionStep::Edit { edit } => self.state.edit(edit)?,
+        }
+        match &step {
+            SessionStep::Action { player, action } if *player == before.actor_to_move() => self.search.advance(action),
+            _ => self.search.clear(),
+        }
+        self.history.push((before, step));
+        Ok(())
+    }
+}
+
+#[wasm_bindgen]
+impl GameSession {
+    /// Resume a session from a game state or from a snapshot exported with `export`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        #[wasm_bindgen(unchecked_param_type = "SessionSnapshot | GameState")] snapshot_json: JsValue,
+    ) -> Result<GameSession, JsValue> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Saved {
+            Session(SessionSnapshot),
+            State(GameState),
+        }
+
+        let mut session = match serde_wasm_bindgen::from_value(snapshot_json)? {
+            Saved::Session(snapshot) => Self {
+                state: snapshot.state,
+                history: snapshot.history,
+                redo: snapshot.redo,
+                search: SearchTree::new(),
+            },
+            Saved::State(state) => Self::from_state(state),
+        };
+        load_state(&mut session.state)?;
+        for (state, _) in &mut session.history {
+            load_state(state)?;
+        }
+        Ok(session)
+    }
+
+    /// Start a computer game (see `new_computer_game`).
+    pub fn computer_game(
+        #[wasm_bindgen(unchecked_param_type = "NewComputerGameRequest")] req_json: JsValue,
+        #[wasm_bindgen(unchecked_param_type = "GameConfig | undefined")] config_json: JsValue,
+    ) -> Result<GameSession, JsValue> {
+        let req: NewComputerGameRequest = serde_wasm_bindgen::from_value(req_json)?;
+        let config = parse_config(config_json)?;
+        let state = GameState::new_computer_game(req.seed, config).map_err(engine_error)?;
+        Ok(Self::from_state(state))
+    }
+
+    /// Start a manual game (see `new_manual_game`).
+    pub fn manual_game(
+        #[wasm_bindgen(unchecked_param_type = "ManualStartRequest")] req_json: JsValue,
+        #[wasm_bindgen(unchecked_param_type = "GameConfig | undefined")] config_json: JsValue,
+    ) -> Result<GameSession, JsValue> {
+        let req: ManualStartRequest = serde_wasm_bindgen::from_value(req_json)?;
+        let config = parse_config(config_json)?;
+        let state = GameState::new_manual_game(
+            req.trump_card,
+            req.player_hand,
+            req.seat,
+            req.starting_player,
+            req.opponent_trumps,
+            config,
+        )
+        .map_err(engine_error)?;
+        Ok(Self::from_state(state))
+    }
+
+    /// Start a manual game from a position in progress (see `new_manual_position`).
+    pub fn manual_position(
+        #[wasm_bindgen(unchecked_param_type = "ManualPositionBuilder")] position_json: JsValue,
+        #[wasm_bindgen(unchecked_param_type = "GameConfig | undefined")] config_json: JsValue,
+    ) -> Result<GameSession, JsValue> {
+        let position: ManualPositionBuilder = serde_wasm_bindgen::from_value(position_json)?;
+        let config = parse_config(config_json)?;
+        let state = position.build(config).map_err(engine_error)?;
+        Ok(Self::from_state(state))
+    }
+
+    /// The current game state.
+    #[wasm_bindgen(unchecked_return_type = "GameState")]
+    pub fn state(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.state).unwrap()
+    }
+
+    /// The session with its history, to save and resume later with `new GameSession(snapshot)`.
+    #[wasm_bindgen(unchecked_return_type = "SessionSnapshot")]
+    pub fn export(&self) -> JsValue {
+        let snapshot = SessionSnapshot {
+            state: self.state.clone(),
+            history: self.history.clone(),
+            redo: self.redo.clone(),
+        };
+        serde_wasm_bindgen::to_value(&snapshot).unwrap()
+    }
+
+    #[wasm_bindgen(unchecked_return_type = "Action[]")]
+    pub fn legal_actions(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.state.legal_actions()).unwrap()
+    }
+
+    #[wasm_bindgen(unchecked_return_type = "PlayerId")]
+    pub fn actor_to_move(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.state.actor_to_move()).unwrap()
+    }
+
+    #[wasm_bindgen(unchecked_return_type = "PlayerId[]")]
+    pub fn eligible_players(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.state.eligible_players()).unwrap()
+    }
+
+    /// The outcome (durak or draw, with finishing order), or null if the game is not over.
+    #[wasm_bindgen(unchecked_return_type = "Outcome | undefined")]
+    pub fn outcome(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.state.outcome()).unwrap()
+    }
+
+    /// The steps played so far, oldest first.
+    #[wasm_bindgen(unchecked_return_type = "SessionStep[]")]
+    pub fn history(&self) -> JsValue {
+        let steps: Vec<&SessionStep> = self.history.iter().map(|(_, step)| step).collect();
+        serde_wasm_bindgen::to_value(&steps).unwrap()
+    }
+
+    /// Apply an action by the actor to move. Clears the redo stack.
+    pub fn apply_action(
+        &mut self,
+        #[wasm_bindgen(unchecked_param_type = "Action")] action_json: JsValue,
+    ) -> Result<(), JsValue> {
+        let action: Action = serde_wasm_bindgen::from_value(action_json)?;
+        let player = self.state.actor_to_move();
+        self.apply_step(SessionStep::Action { player, action }).map_err(engine_error)?;
+        self.redo.clear();
+        Ok(())
+    }
+
+    /// Apply an action submitted by `player` (see `apply_action_as`). Clears the redo stack.
+    pub fn apply_action_as(
+        &mut self,
+        #[wasm_bindgen(unchecked_param_type = "PlayerId")] player_json: JsValue,
+        #[wasm_bindgen(unchecked_param_type = "Action")] action_json: JsValue,
+    ) -> Result<(), JsValue> {
+        let player: PlayerId = serde_wasm_bindgen::from_value(player_json)?;
+        let action: Action = serde_wasm_bindgen::from_value(action_json)?;
+        self.apply_step(SessionStep::Action { player, action }).map_err(engine_error)?;
+        self.redo.clear();
+        Ok(())
+    }
+
+    /// Apply a manual-mode correction (see `edit_state`). Clears the redo stack.
+    pub fn edit(
+        &mut self,
+        #[wasm_bindgen(unchecked_param_type = "Edit")] edit_json: JsValue,
+    ) -> Result<(), JsValue> {
+        let edit: Edit = serde_wasm_bindgen::from_value(edit_json)?;
+        self.apply_step(SessionStep::Edit { edit }).map_err(engine_error)?;
+        self.redo.clear();
+        Ok(())
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.history.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Undo the last step. Returns false if there is nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        let Some((state, step)) = self.history.pop() else {
+            return false;
+        };
+        self.state = state;
+        self.redo.push(step);
+        self.search.clear();
+        true
+    }
+
+    /// Redo the last undone step. Returns false if there is nothing to redo.
+    pub fn redo(&mut self) -> Result<bool, JsValue> {
+        let Some(step) = self.redo.pop() else {
+            return Ok(false);
+        };
+        if let Err(error) = self.apply_step(step.clone()) {
+            self.redo.push(step);
+            return Err(engine_error(error));
+        }
+        Ok(true)
+    }
+
+    /// Search the current state. Unlike the free `solve`, which searches each determinization with
+    /// its own tree, all determinizations share one tree (see `SearchTree`). The tree grows over calls, also across moves played
+    /// by the actor to move, until a correction, an undo, a move out of turn or a change of the
+    /// objective, perspective or depth clears it.
+    #[wasm_bindgen(unchecked_return_type = "SolveAggregate")]
+    pub fn solve(
+        &mut self,
+        #[wasm_bindgen(unchecked_param_type = "SolveOptions | undefined")] options_json: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let options: SolveOptions = if options_json.is_undefined() || options_json.is_null() {
+            SolveOptions::default()
+        } else {
+            serde_wasm_bindgen::from_value(options_json)?
+        };
+        if !self.state.legal_actions().is_empty() {
+            run_search(&mut self.search, &self.state, &options)?;
+        }
+        Ok(serde_wasm_bindgen::to_value(&aggregate_tree(&self.search, &self.state)).unwrap())
+    }
+
+    /// Discard the search tree.
+    pub fn reset_search(&mut self) {
+        self.search.clear();
+    }
+}
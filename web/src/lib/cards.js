@@ -49,6 +49,7 @@ const rankToShort = {
   Queen: 'Q',
   King: 'K',
   Ace: 'A',
+  Joker: 'Joker',
 }
 
 const BASE_URL = (import.meta?.env?.BASE_URL ?? '/').replace(/\/+$/, '/')
@@ -82,6 +83,8 @@ export function suitSymbol(suit) {
 
 export function cardLabel(card) {
   if (!card) return ''
+  // Jokers carry a suit only to tell red (Hearts) and black (Spades) apart
+  if (card.rank === 'Joker') return isRedSuit(card.suit) ? 'Red Joker' : 'Black Joker'
   return `${rankToShort[card.rank] || card.rank}${suitSymbol(card.suit)}`
 }
 